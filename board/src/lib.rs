@@ -2,6 +2,7 @@ extern crate arrayvec;
 use arrayvec::ArrayVec;
 use std::fmt;
 pub mod square;
+pub mod zobrist;
 pub use self::square::{ Square, Piece, Color, Move, Turn, PieceType };
 
 #[derive(Clone)]
@@ -9,8 +10,22 @@ pub struct Board {
     pub squares: ArrayVec<[Square; 100]>,
     pub current_turn: Turn,
     pub board_string_with_turn_bit: String,
+    //Zobrist key of the position, kept up to date by make_move
+    pub hash: u64,
+    //Zobrist hash of the pawns alone, for caching pawn-structure evaluation
+    pub pawn_hash: u64,
+    //Plies played since the start of the game, or since whatever position the board was created from
+    pub ply: u32,
+    //White kingside, white queenside, black kingside and black queenside. The board cannot castle, but keeps
+    //track of the rights for opening books, which tell positions apart by them
+    pub castling_rights: [bool; 4],
+    //The square a pawn passed over with a double step on the last move, as in FEN
+    pub en_passant_square: Option<usize>,
 }
 
+//King and rook squares of each castling right, in the order of Board::castling_rights
+pub const CASTLING_SQUARES: [(Color, usize, usize); 4] = [(Color::White, 85, 88), (Color::White, 85, 81), (Color::Black, 15, 18), (Color::Black, 15, 11)];
+
 impl Board {
 
     pub fn new(board_string: String, current_color: Color) -> Board {
@@ -29,7 +44,72 @@ impl Board {
             Color::White => '1',
             Color::Black => '0',
         });
-        Board { squares, current_turn: Turn { color: current_color }, board_string_with_turn_bit }
+        let hash = helpers::generate_hash(&squares, current_color);
+        let pawn_hash = helpers::generate_pawn_hash(&squares);
+        //Without a record of the moves played, any king and rook still on their starting squares may castle
+        let mut castling_rights = [false; 4];
+        for (castling_index, (color, king_index, rook_index)) in CASTLING_SQUARES.iter().enumerate() {
+            let is_piece = |board_index: usize, piece_type: PieceType| squares[board_index].piece == Some(Piece::new(piece_type, *color));
+            castling_rights[castling_index] = is_piece(*king_index, PieceType::King) && is_piece(*rook_index, PieceType::Rook);
+        }
+        Board { squares, current_turn: Turn { color: current_color }, board_string_with_turn_bit, hash, pawn_hash, ply: 0, castling_rights, en_passant_square: None }
+    }
+
+    //Castling rights and the en passant square are kept for opening books, though the board cannot play
+    //either move. Also accepts EPD, which starts with the same fields and has no move number
+    pub fn from_fen(fen: &str) -> Option<Board> {
+        let mut fields = fen.split_whitespace();
+        let placement = fields.next()?;
+        let current_color = match fields.next()? {
+            "w" => Color::White,
+            "b" => Color::Black,
+            _ => return None,
+        };
+        let ranks: Vec<&str> = placement.split('/').collect();
+        if ranks.len() != 8 {
+            return None;
+        }
+
+        let mut board_string = String::from("0000000000");
+        for rank in ranks {
+            let mut rank_string = String::new();
+            for c in rank.chars() {
+                match c {
+                    '1'..='8' => (0..c.to_digit(10)?).for_each(|_| rank_string.push('-')),
+                    'p' | 'n' | 'b' | 'r' | 'q' | 'k' | 'P' | 'N' | 'B' | 'R' | 'Q' | 'K' => rank_string.push(c),
+                    _ => return None,
+                }
+            }
+            if rank_string.len() != 8 {
+                return None;
+            }
+            board_string.push('0');
+            board_string.push_str(&rank_string);
+            board_string.push('0');
+        }
+        board_string.push_str("0000000000");
+        let mut board = Board::new(board_string, current_color);
+        let castling_field = fields.next().unwrap_or("-");
+        for (castling_index, c) in ['K', 'Q', 'k', 'q'].iter().enumerate() {
+            board.castling_rights[castling_index] &= castling_field.contains(*c);
+        }
+        board.en_passant_square = match fields.next() {
+            Some("-") | None => None,
+            Some(square) => {
+                let mut chars = square.chars();
+                match (chars.next(), chars.next(), chars.next()) {
+                    (Some(file @ 'a'..='h'), Some(rank @ '1'..='8'), None) => Some(helpers::square_to_index(format!("{}{}", file, rank))),
+                    _ => return None,
+                }
+            },
+        };
+        if let Some(move_number) = fields.nth(1).and_then(|field| field.parse::<u32>().ok()) {
+            board.ply = 2 * move_number.saturating_sub(1) + match current_color {
+                Color::White => 0,
+                Color::Black => 1,
+            };
+        }
+        Some(board)
     }
 
     pub fn get_piece_at(&self, index: usize) -> Option<Piece> {
@@ -76,7 +156,27 @@ impl Board {
         match self.get_piece_at(chess_move.from) {
             None => panic!("There is no piece on the square form which the move is being made: {:?}", self.squares[chess_move.from]),
             Some(p) => {
+                if let Some(captured_piece) = self.get_piece_at(chess_move.to) {
+                    self.hash ^= zobrist::get_piece_key(captured_piece, chess_move.to);
+                    if captured_piece.piece_type == PieceType::Pawn {
+                        self.pawn_hash ^= zobrist::get_piece_key(captured_piece, chess_move.to);
+                    }
+                }
+                self.hash ^= zobrist::get_piece_key(p, chess_move.from) ^ zobrist::get_piece_key(p, chess_move.to) ^ zobrist::BLACK_TO_MOVE_KEY;
+                if p.piece_type == PieceType::Pawn {
+                    self.pawn_hash ^= zobrist::get_piece_key(p, chess_move.from) ^ zobrist::get_piece_key(p, chess_move.to);
+                }
                 self.current_turn.toggle();
+                self.ply += 1;
+                for (castling_index, (_, king_index, rook_index)) in CASTLING_SQUARES.iter().enumerate() {
+                    if [*king_index, *rook_index].iter().any(|index| *index == chess_move.from || *index == chess_move.to) {
+                        self.castling_rights[castling_index] = false;
+                    }
+                }
+                self.en_passant_square = match p.piece_type == PieceType::Pawn && chess_move.from.abs_diff(chess_move.to) == 20 {
+                    true => Some((chess_move.from + chess_move.to) / 2),
+                    false => None,
+                };
                 self.set_square(chess_move.from, None);
                 self.set_square(chess_move.to, Some(p));
             }
@@ -101,8 +201,16 @@ impl Board {
         self.board_string_with_turn_bit = next_board_string;
     }
 
-    pub fn clone(&self) -> Board {
-        Board { squares: self.squares.clone(), current_turn: self.current_turn, board_string_with_turn_bit: self.board_string_with_turn_bit.clone() }
+    //Passes the turn without moving a piece, as used by null-move pruning
+    pub fn make_null_move(&mut self) {
+        self.hash ^= zobrist::BLACK_TO_MOVE_KEY;
+        self.current_turn.toggle();
+        self.en_passant_square = None;
+        let turn_bit = match self.current_turn.color {
+            Color::White => "1",
+            Color::Black => "0",
+        };
+        self.board_string_with_turn_bit.replace_range(100..101, turn_bit);
     }
 
     pub fn test_move(&self, chess_move: Move) -> Board {
@@ -110,6 +218,24 @@ impl Board {
         test_board.make_move(chess_move);
         test_board
     }
+
+    //The same position with the ranks flipped, the colors of all pieces swapped and the other side to move
+    pub fn mirror(&self) -> Board {
+        let board_string: String = (0..100)
+            .map(|index| {
+                let c = self.get_piece_char_at(helpers::mirror_index(index));
+                match c.is_ascii_uppercase() {
+                    true => c.to_ascii_lowercase(),
+                    false => c.to_ascii_uppercase(),
+                }
+            })
+            .collect();
+        let mut board = Board::new(board_string, square::get_enemy_color(self.current_turn.color));
+        board.ply = self.ply;
+        board.castling_rights = [self.castling_rights[2], self.castling_rights[3], self.castling_rights[0], self.castling_rights[1]];
+        board.en_passant_square = self.en_passant_square.map(helpers::mirror_index);
+        board
+    }
 }
 
 impl fmt::Display for Board {
@@ -123,14 +249,14 @@ impl fmt::Display for Board {
             {}{}{}{}{}{}{}{}
             {}{}{}{}{}{}{}{}
             {}{}{}{}{}{}{}{}
-        ",self.squares[11 as usize], self.squares[12 as usize], self.squares[13 as usize], self.squares[14 as usize], self.squares[15 as usize], self.squares[16 as usize], self.squares[17 as usize], self.squares[18 as usize],
-        self.squares[21 as usize], self.squares[22 as usize], self.squares[23 as usize], self.squares[24 as usize], self.squares[25 as usize], self.squares[26 as usize], self.squares[27 as usize], self.squares[28 as usize],
-        self.squares[31 as usize], self.squares[32 as usize], self.squares[33 as usize], self.squares[34 as usize], self.squares[35 as usize], self.squares[36 as usize], self.squares[37 as usize], self.squares[38 as usize],
-        self.squares[41 as usize], self.squares[42 as usize], self.squares[43 as usize], self.squares[44 as usize], self.squares[45 as usize], self.squares[46 as usize], self.squares[47 as usize], self.squares[48 as usize],
-        self.squares[51 as usize], self.squares[52 as usize], self.squares[53 as usize], self.squares[54 as usize], self.squares[55 as usize], self.squares[56 as usize], self.squares[57 as usize], self.squares[58 as usize],
-        self.squares[61 as usize], self.squares[62 as usize], self.squares[63 as usize], self.squares[64 as usize], self.squares[65 as usize], self.squares[66 as usize], self.squares[67 as usize], self.squares[68 as usize],
-        self.squares[71 as usize], self.squares[72 as usize], self.squares[73 as usize], self.squares[74 as usize], self.squares[75 as usize], self.squares[76 as usize], self.squares[77 as usize], self.squares[78 as usize],
-        self.squares[81 as usize], self.squares[82 as usize], self.squares[83 as usize], self.squares[84 as usize], self.squares[85 as usize], self.squares[86 as usize], self.squares[87 as usize], self.squares[88 as usize])
+        ",self.squares[11], self.squares[12], self.squares[13], self.squares[14], self.squares[15], self.squares[16], self.squares[17], self.squares[18],
+        self.squares[21], self.squares[22], self.squares[23], self.squares[24], self.squares[25], self.squares[26], self.squares[27], self.squares[28],
+        self.squares[31], self.squares[32], self.squares[33], self.squares[34], self.squares[35], self.squares[36], self.squares[37], self.squares[38],
+        self.squares[41], self.squares[42], self.squares[43], self.squares[44], self.squares[45], self.squares[46], self.squares[47], self.squares[48],
+        self.squares[51], self.squares[52], self.squares[53], self.squares[54], self.squares[55], self.squares[56], self.squares[57], self.squares[58],
+        self.squares[61], self.squares[62], self.squares[63], self.squares[64], self.squares[65], self.squares[66], self.squares[67], self.squares[68],
+        self.squares[71], self.squares[72], self.squares[73], self.squares[74], self.squares[75], self.squares[76], self.squares[77], self.squares[78],
+        self.squares[81], self.squares[82], self.squares[83], self.squares[84], self.squares[85], self.squares[86], self.squares[87], self.squares[88])
     }
 }
 
@@ -166,6 +292,32 @@ pub mod helpers {
         String::from(square)
     }
 
+    //The square with the same file on the opposite rank
+    pub fn mirror_index(index: usize) -> usize {
+        (9 - index / 10) * 10 + index % 10
+    }
+
+    pub fn generate_hash(squares: &[Square], current_color: Color) -> u64 {
+        let piece_keys = squares.iter()
+            .enumerate()
+            .filter_map(|(i, square)| square.piece.map(|p| zobrist::get_piece_key(p, i)))
+            .fold(0, |hash, key| hash ^ key);
+        match current_color {
+            Color::White => piece_keys,
+            Color::Black => piece_keys ^ zobrist::BLACK_TO_MOVE_KEY,
+        }
+    }
+
+    pub fn generate_pawn_hash(squares: &[Square]) -> u64 {
+        squares.iter()
+            .enumerate()
+            .filter_map(|(i, square)| match square.piece {
+                Some(p) if p.piece_type == PieceType::Pawn => Some(zobrist::get_piece_key(p, i)),
+                _ => None,
+            })
+            .fold(0, |hash, key| hash ^ key)
+    }
+
     pub fn generate_square_from_string(square: char) -> Square {
         match square {
             'p' => Square::new(Piece::new(PieceType::Pawn, Color::Black)),
@@ -430,6 +582,163 @@ mod tests {
             }
         }
         
+        mod hash {
+            use super::*;
+
+            #[test]
+            fn it_matches_a_freshly_generated_hash_after_moves() {
+                let board_string = String::from("00000000000rnbqkbnr00pppppppp00--------00--------00--------00--------00PPPPPPPP00RNBQKBNR00000000000");
+                let mut board: Board = Board::new(board_string, Color::White);
+                board.make_move(Move::from_chess_move((String::from("e2"), String::from("e4"))));
+                board.make_move(Move::from_chess_move((String::from("d7"), String::from("d5"))));
+                board.make_move(Move::from_chess_move((String::from("e4"), String::from("d5"))));
+                assert_eq!(board.hash, helpers::generate_hash(&board.squares, board.current_turn.color));
+            }
+
+            #[test]
+            fn it_is_equal_for_transpositions() {
+                let board_string = String::from("00000000000rnbqkbnr00pppppppp00--------00--------00--------00--------00PPPPPPPP00RNBQKBNR00000000000");
+                let mut first_board: Board = Board::new(board_string.clone(), Color::White);
+                first_board.make_move(Move::from_chess_move((String::from("g1"), String::from("f3"))));
+                first_board.make_move(Move::from_chess_move((String::from("g8"), String::from("f6"))));
+                first_board.make_move(Move::from_chess_move((String::from("b1"), String::from("c3"))));
+                let mut second_board: Board = Board::new(board_string, Color::White);
+                second_board.make_move(Move::from_chess_move((String::from("b1"), String::from("c3"))));
+                second_board.make_move(Move::from_chess_move((String::from("g8"), String::from("f6"))));
+                second_board.make_move(Move::from_chess_move((String::from("g1"), String::from("f3"))));
+                assert_eq!(first_board.hash, second_board.hash);
+            }
+
+            #[test]
+            fn it_keeps_the_pawn_hash_in_step_with_the_pawns() {
+                let board_string = String::from("00000000000rnbqkbnr00pppppppp00--------00--------00--------00--------00PPPPPPPP00RNBQKBNR00000000000");
+                let mut board: Board = Board::new(board_string, Color::White);
+                let starting_pawn_hash = board.pawn_hash;
+                board.make_move(Move::from_chess_move((String::from("g1"), String::from("f3"))));
+                assert_eq!(board.pawn_hash, starting_pawn_hash);
+
+                board.make_move(Move::from_chess_move((String::from("e7"), String::from("e5"))));
+                board.make_move(Move::from_chess_move((String::from("f3"), String::from("e5"))));
+                assert_ne!(board.pawn_hash, starting_pawn_hash);
+                assert_eq!(board.pawn_hash, helpers::generate_pawn_hash(&board.squares));
+            }
+
+            #[test]
+            fn it_depends_on_the_side_to_move() {
+                let board_string = String::from("00000000000rnbqkbnr00pppppppp00--------00--------00--------00--------00PPPPPPPP00RNBQKBNR00000000000");
+                let white_board: Board = Board::new(board_string.clone(), Color::White);
+                let black_board: Board = Board::new(board_string, Color::Black);
+                assert_ne!(white_board.hash, black_board.hash);
+            }
+        }
+
+        mod castling_rights {
+            use super::*;
+
+            #[test]
+            fn it_loses_castling_rights_for_good_once_the_king_or_rook_moves() {
+                let mut board = Board::from_fen("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1").unwrap();
+                assert_eq!(board.castling_rights, [true; 4]);
+                board.make_move(Move::from_chess_move((String::from("e1"), String::from("e2"))));
+                board.make_move(Move::from_chess_move((String::from("h8"), String::from("h7"))));
+                board.make_move(Move::from_chess_move((String::from("e2"), String::from("e1"))));
+                board.make_move(Move::from_chess_move((String::from("h7"), String::from("h8"))));
+                assert_eq!(board.castling_rights, [false, false, false, true]);
+                board.make_move(Move::from_chess_move((String::from("a1"), String::from("a8"))));
+                assert_eq!(board.castling_rights, [false; 4]);
+            }
+
+            #[test]
+            fn it_records_the_square_a_pawn_passed_over() {
+                let mut board = Board::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap();
+                board.make_move(Move::from_chess_move((String::from("e2"), String::from("e4"))));
+                assert_eq!(board.en_passant_square, Some(helpers::square_to_index(String::from("e3"))));
+                board.make_move(Move::from_chess_move((String::from("d7"), String::from("d6"))));
+                assert_eq!(board.en_passant_square, None);
+                board.make_move(Move::from_chess_move((String::from("a2"), String::from("a4"))));
+                assert_eq!(board.mirror().en_passant_square, Some(helpers::square_to_index(String::from("a6"))));
+            }
+        }
+
+        mod make_null_move {
+            use super::*;
+
+            #[test]
+            fn it_passes_the_turn_without_moving_a_piece() {
+                let board_string = String::from("00000000000rnbqkbnr00pppppppp00--------00--------00--------00--------00PPPPPPPP00RNBQKBNR00000000000");
+                let mut board: Board = Board::new(board_string.clone(), Color::White);
+                board.make_null_move();
+                let black_board: Board = Board::new(board_string, Color::Black);
+                assert_eq!(board.current_turn.color, Color::Black);
+                assert_eq!(board.hash, black_board.hash);
+                assert_eq!(board.board_string_with_turn_bit, black_board.board_string_with_turn_bit);
+            }
+        }
+
+        mod from_fen {
+            use super::*;
+
+            #[test]
+            fn it_reads_the_starting_position() {
+                let board = Board::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap();
+                let board_string = String::from("00000000000rnbqkbnr00pppppppp00--------00--------00--------00--------00PPPPPPPP00RNBQKBNR00000000000");
+                let expected_board: Board = Board::new(board_string, Color::White);
+                assert_eq!(board.board_string_with_turn_bit, expected_board.board_string_with_turn_bit);
+                assert_eq!(board.hash, expected_board.hash);
+                assert_eq!(board.ply, 0);
+            }
+
+            #[test]
+            fn it_counts_plies_from_the_move_number() {
+                let board = Board::from_fen("rnbqkbnr/pp1ppppp/8/2p5/4P3/5N2/PPPP1PPP/RNBQKB1R b KQkq - 1 2").unwrap();
+                assert_eq!(board.ply, 3);
+                assert_eq!(board.test_move(Move::from_chess_move((String::from("d7"), String::from("d6")))).ply, 4);
+            }
+
+            #[test]
+            fn it_reads_epd_with_black_to_move() {
+                let board = Board::from_fen("4k3/8/8/3p4/8/8/8/4K3 b - - c9 \"1/2-1/2\";").unwrap();
+                assert_eq!(board.current_turn.color, Color::Black);
+                assert!(match board.get_piece_at(helpers::square_to_index(String::from("d5"))) {
+                    Some(p) => p.piece_type == PieceType::Pawn && p.color == Color::Black,
+                    None => false,
+                });
+            }
+
+            #[test]
+            fn it_reads_castling_rights_and_the_en_passant_square() {
+                let board = Board::from_fen("rnbqkbnr/ppp1pppp/8/3p4/4P3/8/PPPP1PPP/RNBQKBNR w Kq d6 0 2").unwrap();
+                assert_eq!(board.castling_rights, [true, false, false, true]);
+                assert_eq!(board.en_passant_square, Some(helpers::square_to_index(String::from("d6"))));
+
+                //Rights are only kept while the king and rook are still in place
+                let board = Board::from_fen("4k3/8/8/8/8/8/8/R3K1R1 w KQkq - 0 1").unwrap();
+                assert_eq!(board.castling_rights, [false, true, false, false]);
+                assert!(Board::from_fen("4k3/8/8/8/8/8/8/4K3 w - e9 0 1").is_none());
+            }
+
+            #[test]
+            fn it_rejects_malformed_positions() {
+                assert!(Board::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP w").is_none());
+                assert!(Board::from_fen("rnbqkbnr/pppppppp/9/8/8/8/PPPPPPPP/RNBQKBNR w").is_none());
+                assert!(Board::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR").is_none());
+            }
+        }
+
+        mod mirror {
+            use super::*;
+
+            #[test]
+            fn it_flips_the_ranks_and_swaps_the_colors() {
+                let board = Board::from_fen("r1bqkb1r/pppp1ppp/2n2n2/4p3/2B1P3/5N2/PPPP1PPP/RNBQK2R w - - 0 1").unwrap();
+                let expected_board = Board::from_fen("rnbqk2r/pppp1ppp/5n2/2b1p3/4P3/2N2N2/PPPP1PPP/R1BQKB1R b - - 0 1").unwrap();
+                let mirrored_board = board.mirror();
+                assert_eq!(mirrored_board.board_string_with_turn_bit, expected_board.board_string_with_turn_bit);
+                assert_eq!(mirrored_board.hash, expected_board.hash);
+                assert_eq!(mirrored_board.mirror().board_string_with_turn_bit, board.board_string_with_turn_bit);
+            }
+        }
+
         mod test_move {
             use super::*;
 
@@ -443,10 +752,7 @@ mod tests {
                     None => false,
                 });
 
-                assert!(match tested_board.get_piece_at(75) {
-                    Some(_) => false,
-                    None => true,
-                });
+                assert!(tested_board.get_piece_at(75).is_none());
             }
         }
     }
@@ -1,4 +1,3 @@
-use std::rc::Rc;
 use std::fmt;
 use super::helpers;
 use super::Board;
@@ -69,28 +68,39 @@ impl Piece {
         Piece { piece_type, color }
     }
 
-    pub fn get_moves(&self, board_index: usize, board: Rc<Board>) -> Vec<Move> {
-        let legal_moves: Vec<Move> = match self.piece_type {
-            PieceType::Pawn => valid_moves::get_pawn_moves(board_index as i8, board.clone(), self.color),
-            PieceType::Knight => valid_moves::get_knight_moves(board_index as i8, board.clone(), self.color),
-            PieceType::Bishop => valid_moves::get_bishop_moves(board_index as i8, board.clone(), self.color),
-            PieceType::Rook => valid_moves::get_rook_moves(board_index as i8, board.clone(), self.color),
-            PieceType::Queen => valid_moves::get_queen_moves(board_index as i8, board.clone(), self.color),
-            PieceType::King => valid_moves::get_king_moves(board_index as i8, board.clone(), self.color),
-        }
+    pub fn get_moves(&self, board_index: usize, board: &Board) -> Vec<Move> {
+        let legal_moves: Vec<Move> = self.get_pseudo_legal_moves(board_index, board)
             .into_iter()
-            .filter(|pre_check_move| {
-                let mut test_board: Board = board.test_move(Move::new(pre_check_move.from, pre_check_move.to));
-                test_board.current_turn.toggle();
-                !is_check(test_board)
-            })
+            .filter(|pre_check_move| is_legal_move(board, pre_check_move))
             .collect();
 
         legal_moves
     }
+
+    //Moves that follow the piece's movement rules but may leave its own king in check
+    pub fn get_pseudo_legal_moves(&self, board_index: usize, board: &Board) -> Vec<Move> {
+        match self.piece_type {
+            PieceType::Pawn => valid_moves::get_pawn_moves(board_index as i8, board, self.color),
+            PieceType::Knight => valid_moves::get_knight_moves(board_index as i8, board, self.color),
+            PieceType::Bishop => valid_moves::get_bishop_moves(board_index as i8, board, self.color),
+            PieceType::Rook => valid_moves::get_rook_moves(board_index as i8, board, self.color),
+            PieceType::Queen => valid_moves::get_queen_moves(board_index as i8, board, self.color),
+            PieceType::King => valid_moves::get_king_moves(board_index as i8, board, self.color),
+        }
+    }
+
+    pub fn get_pseudo_legal_captures(&self, board_index: usize, board: &Board) -> Vec<Move> {
+        valid_moves::get_captures(board_index as i8, board, *self)
+    }
+
+    pub fn get_pseudo_legal_quiet_moves(&self, board_index: usize, board: &Board) -> Vec<Move> {
+        valid_moves::get_quiet_moves(board_index as i8, board, *self)
+    }
 }
 
+#[derive(Copy, Clone)]
 #[derive(Debug)]
+#[derive(PartialEq)]
 pub struct Move {
     pub from: usize,
     pub to: usize,
@@ -99,7 +109,7 @@ pub struct Move {
 impl Move {
 
     pub fn new(from: usize, to: usize) -> Move {
-        Move { from: from as usize, to: to as usize }
+        Move { from, to }
     }
 
     pub fn from_chess_move(chess_move: (String, String)) -> Move {
@@ -131,15 +141,12 @@ pub struct Turn {
 
 impl Turn {
     pub fn toggle(&mut self) {
-        self.color = match self.color {
-            Color::White => Color::Black,
-            _ => Color::White,
-        }
+        self.color = get_enemy_color(self.color);
     }
 }
 
 pub mod valid_moves {
-    use super::{ Board, Rc, Move, Color };
+    use super::{ Board, Move, Color, Piece, PieceType };
 
     const BISHOP_MOVE_DIRECTIONS: [i8; 4] = [9, 11, -9, -11];
     const KNIGHT_STEPPING_MOVES: [i8; 8] = [-12, -21, -19, -8, 12, 21, 19, 8];
@@ -151,10 +158,10 @@ pub mod valid_moves {
     const BLACK_PAWN_CAPTURE_DIRECTIONS: [i8; 2] = [9, 11];
 
     pub fn is_out_of_bounds(board_index: i8) -> bool {
-        board_index < 11 || board_index > 88 || board_index % 10 == 0 || board_index % 10 == 9
+        !(11..=88).contains(&board_index) || board_index % 10 == 0 || board_index % 10 == 9
     }
 
-    pub fn get_pawn_moves(board_index: i8, board: Rc<Board>, color: Color) -> Vec<Move> {
+    pub fn get_pawn_moves(board_index: i8, board: &Board, color: Color) -> Vec<Move> {
         let move_directions: [i8; 1] = match color {
             Color::White => WHITE_PAWN_MOVE_DIRECTIONS,
             Color::Black => BLACK_PAWN_MOVE_DIRECTIONS,
@@ -165,26 +172,23 @@ pub mod valid_moves {
         };
         let double_move_directions: [i8; 1] = match color {
             Color::White => match board_index {
-                71 ... 78 => [-20],
+                71..=78 => [-20],
                 _ => [0],
             },
             Color::Black => match board_index {
-                21 ... 28 => [20],
+                21..=28 => [20],
                 _ => [0],
             },
         };
 
-        let legal_moves: Vec<Move> = move_directions.into_iter()
+        let legal_moves: Vec<Move> = move_directions.iter()
             .map(|step| board_index + step)
             .filter(|to| {
                 if is_out_of_bounds(*to) { return false; }
-                match board.get_piece_at(*to as usize) {
-                    Some(_) => false,
-                    None => true
-                }
+                board.get_piece_at(*to as usize).is_none()
             })
             .chain(
-                capture_directions.into_iter()
+                capture_directions.iter()
                     .map(|step| board_index + step)
                     .filter(|to| {
                         if is_out_of_bounds(*to) { return false; }
@@ -195,17 +199,12 @@ pub mod valid_moves {
                     })
             )
             .chain(
-                double_move_directions.into_iter()
+                double_move_directions.iter()
                     .map(|step| board_index + step)
                     .filter(|to| {
                         if is_out_of_bounds(*to) { return false; }
-                        match board.get_piece_at(*to as usize) {
-                            Some(_) => false,
-                            None => match board.get_piece_at((board_index as i8 + ((*to - board_index) / 2)) as usize) {
-                                Some(_) => false,
-                                None => true,
-                            }
-                        }
+                        board.get_piece_at(*to as usize).is_none()
+                            && board.get_piece_at((board_index + ((*to - board_index) / 2)) as usize).is_none()
                     })
             )
             .map(|to| Move::new(board_index as usize, to as usize))
@@ -214,8 +213,8 @@ pub mod valid_moves {
         legal_moves
     }
 
-    pub fn get_knight_moves(board_index: i8, board: Rc<Board>, color: Color) -> Vec<Move> {
-        let legal_moves: Vec<Move> = KNIGHT_STEPPING_MOVES.into_iter()
+    pub fn get_knight_moves(board_index: i8, board: &Board, color: Color) -> Vec<Move> {
+        let legal_moves: Vec<Move> = KNIGHT_STEPPING_MOVES.iter()
             .map(|step| board_index + step)
             .filter(|to| {
                 if is_out_of_bounds(*to) { return false; }
@@ -230,13 +229,13 @@ pub mod valid_moves {
         legal_moves
     }
 
-    pub fn get_bishop_moves(board_index: i8, board: Rc<Board>, color: Color) -> Vec<Move> {
+    pub fn get_bishop_moves(board_index: i8, board: &Board, color: Color) -> Vec<Move> {
         let mut legal_moves: Vec<Move> = Vec::new();
 
-        for direction in BISHOP_MOVE_DIRECTIONS.into_iter() {
+        for direction in BISHOP_MOVE_DIRECTIONS.iter() {
             let mut to: i8 = board_index;
             loop {
-                to = to + direction;
+                to += direction;
                 if is_out_of_bounds(to) { break; }
                 match board.get_piece_at(to as usize) {
                     Some(p) => {
@@ -255,13 +254,13 @@ pub mod valid_moves {
         legal_moves
     }
 
-    pub fn get_rook_moves(board_index: i8, board: Rc<Board>, color: Color) -> Vec<Move> {
+    pub fn get_rook_moves(board_index: i8, board: &Board, color: Color) -> Vec<Move> {
         let mut legal_moves: Vec<Move> = Vec::new();
 
-        for direction in ROOK_MOVE_DIRECTIONS.into_iter() {
+        for direction in ROOK_MOVE_DIRECTIONS.iter() {
             let mut to: i8 = board_index;
             loop {
-                to = to + direction;
+                to += direction;
                 if is_out_of_bounds(to) { break; }
                 match board.get_piece_at(to as usize) {
                     Some(p) => {
@@ -280,13 +279,13 @@ pub mod valid_moves {
         legal_moves
     }
 
-    pub fn get_queen_moves(board_index: i8, board: Rc<Board>, color: Color) -> Vec<Move> {
+    pub fn get_queen_moves(board_index: i8, board: &Board, color: Color) -> Vec<Move> {
         let mut legal_moves: Vec<Move> = Vec::new();
 
-        for direction in KING_QUEEN_MOVE_DIRECTIONS.into_iter() {
+        for direction in KING_QUEEN_MOVE_DIRECTIONS.iter() {
             let mut to: i8 = board_index;
             loop {
-                to = to + direction;
+                to += direction;
                 if is_out_of_bounds(to) { break; }
                 match board.get_piece_at(to as usize) {
                     Some(p) => {
@@ -305,8 +304,8 @@ pub mod valid_moves {
         legal_moves
     }
 
-    pub fn get_king_moves(board_index: i8, board: Rc<Board>, color: Color) -> Vec<Move> {
-        let legal_moves: Vec<Move> = KING_QUEEN_MOVE_DIRECTIONS.into_iter()
+    pub fn get_king_moves(board_index: i8, board: &Board, color: Color) -> Vec<Move> {
+        let legal_moves: Vec<Move> = KING_QUEEN_MOVE_DIRECTIONS.iter()
             .map(|step| board_index + step)
             .filter(|to| {
                 if is_out_of_bounds(*to) { return false; }
@@ -320,44 +319,164 @@ pub mod valid_moves {
 
         legal_moves
     }
+
+    //Captures and quiet moves come in the same order as from the piece's full move list, but each
+    //is generated on its own so that a search cut off by a capture never has to generate the rest
+    pub fn get_captures(board_index: i8, board: &Board, piece: Piece) -> Vec<Move> {
+        let targets: Vec<i8> = match piece.piece_type {
+            PieceType::Pawn => {
+                let capture_directions: [i8; 2] = match piece.color {
+                    Color::White => WHITE_PAWN_CAPTURE_DIRECTIONS,
+                    Color::Black => BLACK_PAWN_CAPTURE_DIRECTIONS,
+                };
+                get_step_targets(board_index, &capture_directions)
+            },
+            PieceType::Knight => get_step_targets(board_index, &KNIGHT_STEPPING_MOVES),
+            PieceType::Bishop => get_ray_ends(board_index, board, &BISHOP_MOVE_DIRECTIONS),
+            PieceType::Rook => get_ray_ends(board_index, board, &ROOK_MOVE_DIRECTIONS),
+            PieceType::Queen => get_ray_ends(board_index, board, &KING_QUEEN_MOVE_DIRECTIONS),
+            PieceType::King => get_step_targets(board_index, &KING_QUEEN_MOVE_DIRECTIONS),
+        };
+
+        targets.into_iter()
+            .filter(|to| match board.get_piece_at(*to as usize) {
+                Some(p) => p.color != piece.color,
+                None => false,
+            })
+            .map(|to| Move::new(board_index as usize, to as usize))
+            .collect()
+    }
+
+    pub fn get_quiet_moves(board_index: i8, board: &Board, piece: Piece) -> Vec<Move> {
+        let targets: Vec<i8> = match piece.piece_type {
+            PieceType::Pawn => return get_pawn_moves(board_index, board, piece.color)
+                .into_iter()
+                .filter(|pawn_move| board.get_piece_at(pawn_move.to).is_none())
+                .collect(),
+            PieceType::Knight => get_step_targets(board_index, &KNIGHT_STEPPING_MOVES),
+            PieceType::Bishop => get_ray_squares(board_index, board, &BISHOP_MOVE_DIRECTIONS),
+            PieceType::Rook => get_ray_squares(board_index, board, &ROOK_MOVE_DIRECTIONS),
+            PieceType::Queen => get_ray_squares(board_index, board, &KING_QUEEN_MOVE_DIRECTIONS),
+            PieceType::King => get_step_targets(board_index, &KING_QUEEN_MOVE_DIRECTIONS),
+        };
+
+        targets.into_iter()
+            .filter(|to| board.get_piece_at(*to as usize).is_none())
+            .map(|to| Move::new(board_index as usize, to as usize))
+            .collect()
+    }
+
+    fn get_step_targets(board_index: i8, steps: &[i8]) -> Vec<i8> {
+        steps.iter()
+            .map(|step| board_index + step)
+            .filter(|to| !is_out_of_bounds(*to))
+            .collect()
+    }
+
+    //The first occupied square along each direction, the only one a slider can capture on
+    fn get_ray_ends(board_index: i8, board: &Board, directions: &[i8]) -> Vec<i8> {
+        directions.iter()
+            .filter_map(|direction| {
+                let mut to: i8 = board_index + direction;
+                while !is_out_of_bounds(to) {
+                    if board.get_piece_at(to as usize).is_some() {
+                        return Some(to);
+                    }
+                    to += direction;
+                }
+                None
+            })
+            .collect()
+    }
+
+    //The empty squares along each direction up to the first piece
+    fn get_ray_squares(board_index: i8, board: &Board, directions: &[i8]) -> Vec<i8> {
+        let mut squares: Vec<i8> = Vec::new();
+
+        for direction in directions.iter() {
+            let mut to: i8 = board_index + direction;
+            while !is_out_of_bounds(to) && board.get_piece_at(to as usize).is_none() {
+                squares.push(to);
+                to += direction;
+            }
+        }
+
+        squares
+    }
+}
+
+//Whether a pseudo-legal move keeps the mover's king out of check
+pub fn is_legal_move(board: &Board, chess_move: &Move) -> bool {
+    let test_board: Board = board.test_move(*chess_move);
+    !is_in_check(&test_board, board.current_turn.color)
 }
 
 pub fn is_check(board: Board) -> bool {
-    let current_color: Color = board.current_turn.color;
-    let king_position: usize = board.clone().squares.into_iter()
+    is_in_check(&board, board.current_turn.color)
+}
+
+pub fn is_in_check(board: &Board, color: Color) -> bool {
+    let king_position: usize = board.squares.iter()
         .position(|square| match square.piece {
-            Some(p) => p.piece_type == PieceType::King && p.color == current_color,
+            Some(p) => p.piece_type == PieceType::King && p.color == color,
             None => false,
         })
         .unwrap();
 
-    match valid_moves::get_bishop_moves(king_position as i8, Rc::new(board.clone()), current_color)
-        .iter()
-        .filter_map(|bishop_move| board.get_piece_at(bishop_move.to))
-        .find(|piece| (piece.piece_type == PieceType::Bishop || piece.piece_type == PieceType::Queen) && piece.color != current_color) {
-            Some(_) => true,
-            None => match valid_moves::get_rook_moves(king_position as i8, Rc::new(board.clone()), current_color).iter()
-                        .filter_map(|rook_move| board.get_piece_at(rook_move.to))
-                        .find(|piece| (piece.piece_type == PieceType::Rook || piece.piece_type == PieceType::Queen) && piece.color != current_color) {
-                            Some(_) => true,
-                            None => match valid_moves::get_knight_moves(king_position as i8, Rc::new(board.clone()), current_color).iter()
-                                        .filter_map(|knight_move| board.get_piece_at(knight_move.to))
-                                        .find(|piece| piece.piece_type == PieceType::Knight && piece.color != current_color) {
-                                            Some(_) => true,
-                                            None => match valid_moves::get_king_moves(king_position as i8, Rc::new(board.clone()), current_color).iter()
-                                                        .filter_map(|king_move| board.get_piece_at(king_move.to))
-                                                        .find(|piece| piece.piece_type == PieceType::King && piece.color != current_color) {
-                                                            Some(_) => true,
-                                                            None => match valid_moves::get_pawn_moves(king_position as i8, Rc::new(board.clone()), current_color).iter()
-                                                                        .filter_map(|pawn_move| board.get_piece_at(pawn_move.to))
-                                                                        .find(|piece| piece.piece_type == PieceType::Pawn && piece.color != current_color) {
-                                                                            Some(_) => true,
-                                                                            None => false,
-                                                                        }
-                                                        }
-                                        }
-                        }
-        }
+    is_attacked(board, king_position, color)
+}
+
+//Whether a piece of the given color standing on board_index could be captured by the other side
+pub fn is_attacked(board: &Board, board_index: usize, color: Color) -> bool {
+    let is_attacked_by = |attacking_moves: Vec<Move>, attacker_types: &[PieceType]| {
+        attacking_moves.iter()
+            .filter_map(|attacking_move| board.get_piece_at(attacking_move.to))
+            .any(|piece| attacker_types.contains(&piece.piece_type) && piece.color != color)
+    };
+
+    is_attacked_by(valid_moves::get_bishop_moves(board_index as i8, board, color), &[PieceType::Bishop, PieceType::Queen])
+        || is_attacked_by(valid_moves::get_rook_moves(board_index as i8, board, color), &[PieceType::Rook, PieceType::Queen])
+        || is_attacked_by(valid_moves::get_knight_moves(board_index as i8, board, color), &[PieceType::Knight])
+        || is_attacked_by(valid_moves::get_king_moves(board_index as i8, board, color), &[PieceType::King])
+        || is_attacked_by(valid_moves::get_pawn_moves(board_index as i8, board, color), &[PieceType::Pawn])
+}
+
+pub fn get_enemy_color(color: Color) -> Color {
+    match color {
+        Color::White => Color::Black,
+        Color::Black => Color::White,
+    }
+}
+
+//Counted from the color's own back rank, which is rank 1
+pub fn get_relative_rank(board_index: usize, color: Color) -> usize {
+    match color {
+        Color::White => 9 - board_index / 10,
+        Color::Black => board_index / 10,
+    }
+}
+
+//The board index step that takes a pawn of the given color one rank forward
+pub fn get_forward_step(color: Color) -> i32 {
+    match color {
+        Color::White => -10,
+        Color::Black => 10,
+    }
+}
+
+//Takes any index so that callers can step off the board without checking first
+pub fn is_pawn(board: &Board, index: i32, color: Color) -> bool {
+    if !(0..100).contains(&index) {
+        return false;
+    }
+    match board.get_piece_at(index as usize) {
+        Some(p) => p.piece_type == PieceType::Pawn && p.color == color,
+        None => false,
+    }
+}
+
+pub fn has_pawn_on_file(board: &Board, file: i32, color: Color) -> bool {
+    (1..=8).any(|row| is_pawn(board, row * 10 + file, color))
 }
 
 #[cfg(test)]
@@ -380,8 +499,8 @@ mod tests {
                 board.make_move(Move::from_chess_move((String::from("e5"), String::from("e4"))));
                 board.make_move(Move::from_chess_move((String::from("a2"), String::from("a4"))));
                 board.make_move(Move::from_chess_move((String::from("e4"), String::from("e3"))));
-                let pawn = board.get_piece_at(helpers::square_to_index(String::from("d2")) as usize).unwrap();
-                let legal_moves: Vec<Move> = pawn.get_moves(helpers::square_to_index(String::from("d2")) as usize, Rc::new(board));
+                let pawn = board.get_piece_at(helpers::square_to_index(String::from("d2"))).unwrap();
+                let legal_moves: Vec<Move> = pawn.get_moves(helpers::square_to_index(String::from("d2")), &board);
                 assert_eq!(legal_moves.len(), 3);
             }
         }
@@ -393,8 +512,8 @@ mod tests {
             fn it_should_return_a_list_of_moves_from_a_given_position() {
                 let board_string = String::from("00000000000rnbqkbnr00pppppppp00--------00--------00--------00--------00PPPPPPPP00RNBQKBNR00000000000");
                 let board: Board = Board::new(board_string, Color::White);
-                let knight = board.get_piece_at(helpers::square_to_index(String::from("g1")) as usize).unwrap();
-                let legal_moves: Vec<Move> = knight.get_moves(helpers::square_to_index(String::from("g1")) as usize, Rc::new(board));
+                let knight = board.get_piece_at(helpers::square_to_index(String::from("g1"))).unwrap();
+                let legal_moves: Vec<Move> = knight.get_moves(helpers::square_to_index(String::from("g1")), &board);
                 assert_eq!(legal_moves.len(), 2);
             }
         }
@@ -408,8 +527,8 @@ mod tests {
                 let mut board: Board = Board::new(board_string, Color::White);
                 board.make_move(Move::from_chess_move((String::from("e2"), String::from("e4"))));
                 board.make_move(Move::from_chess_move((String::from("e7"), String::from("e5"))));
-                let bishop = board.get_piece_at(helpers::square_to_index(String::from("f1")) as usize).unwrap();
-                let legal_moves: Vec<Move> = bishop.get_moves(helpers::square_to_index(String::from("f1")) as usize, Rc::new(board));
+                let bishop = board.get_piece_at(helpers::square_to_index(String::from("f1"))).unwrap();
+                let legal_moves: Vec<Move> = bishop.get_moves(helpers::square_to_index(String::from("f1")), &board);
                 assert_eq!(legal_moves.len(), 5);
             }
         }
@@ -425,8 +544,8 @@ mod tests {
                 board.make_move(Move::from_chess_move((String::from("e7"), String::from("e5"))));
                 board.make_move(Move::from_chess_move((String::from("g1"), String::from("f3"))));
                 board.make_move(Move::from_chess_move((String::from("d7"), String::from("d5"))));
-                let rook = board.get_piece_at(88 as usize).unwrap();
-                let legal_moves: Vec<Move> = rook.get_moves(88 as usize, Rc::new(board));
+                let rook = board.get_piece_at(88).unwrap();
+                let legal_moves: Vec<Move> = rook.get_moves(88, &board);
                 assert_eq!(legal_moves.len(), 3);
             }
         }
@@ -442,8 +561,8 @@ mod tests {
                 board.make_move(Move::from_chess_move((String::from("e7"), String::from("e5"))));
                 board.make_move(Move::from_chess_move((String::from("d1"), String::from("g4"))));
                 board.make_move(Move::from_chess_move((String::from("h7"), String::from("h4"))));
-                let queen = board.get_piece_at(57 as usize).unwrap();
-                let legal_moves: Vec<Move> = queen.get_moves(57 as usize, Rc::new(board));
+                let queen = board.get_piece_at(57).unwrap();
+                let legal_moves: Vec<Move> = queen.get_moves(57, &board);
                 assert_eq!(legal_moves.len(), 14);
             }
         }
@@ -458,12 +577,64 @@ mod tests {
                 board.make_move(Move::from_chess_move((String::from("e7"), String::from("e5"))));
                 board.make_move(Move::from_chess_move((String::from("e1"), String::from("e2"))));
                 board.make_move(Move::from_chess_move((String::from("h7"), String::from("h4"))));
-                let king = board.get_piece_at(75 as usize).unwrap();
-                let legal_moves: Vec<Move> = king.get_moves(75 as usize, Rc::new(board));
+                let king = board.get_piece_at(75).unwrap();
+                let legal_moves: Vec<Move> = king.get_moves(75, &board);
                 assert_eq!(legal_moves.len(), 4);     
             }
         }
 
+        mod get_captures {
+            use super::*;
+
+            #[test]
+            fn it_only_returns_moves_onto_enemy_pieces() {
+                let board_string = String::from("00000000000rnbqkbnr00pppppppp00--------00--------00--------00--------00PPPPPPPP00RNBQKBNR00000000000");
+                let mut board: Board = Board::new(board_string, Color::White);
+                board.make_move(Move::from_chess_move((String::from("e2"), String::from("e4"))));
+                board.make_move(Move::from_chess_move((String::from("d7"), String::from("d5"))));
+                board.make_move(Move::from_chess_move((String::from("d1"), String::from("h5"))));
+                board.make_move(Move::from_chess_move((String::from("g8"), String::from("f6"))));
+                let queen = board.get_piece_at(helpers::square_to_index(String::from("h5"))).unwrap();
+                let captures: Vec<Move> = queen.get_pseudo_legal_captures(helpers::square_to_index(String::from("h5")), &board);
+                let pawn = board.get_piece_at(helpers::square_to_index(String::from("e4"))).unwrap();
+                let pawn_captures: Vec<Move> = pawn.get_pseudo_legal_captures(helpers::square_to_index(String::from("e4")), &board);
+
+                assert_eq!(captures, vec![
+                    Move::from_chess_move((String::from("h5"), String::from("d5"))),
+                    Move::from_chess_move((String::from("h5"), String::from("f7"))),
+                    Move::from_chess_move((String::from("h5"), String::from("h7"))),
+                ]);
+                assert_eq!(pawn_captures, vec![Move::from_chess_move((String::from("e4"), String::from("d5")))]);
+            }
+        }
+
+        mod get_quiet_moves {
+            use super::*;
+
+            #[test]
+            fn it_splits_the_pseudo_legal_moves_with_the_captures() {
+                let board_string = String::from("00000000000rnbqkbnr00pppppppp00--------00--------00--------00--------00PPPPPPPP00RNBQKBNR00000000000");
+                let mut board: Board = Board::new(board_string, Color::White);
+                board.make_move(Move::from_chess_move((String::from("e2"), String::from("e4"))));
+                board.make_move(Move::from_chess_move((String::from("d7"), String::from("d5"))));
+                board.make_move(Move::from_chess_move((String::from("d1"), String::from("h5"))));
+                board.make_move(Move::from_chess_move((String::from("g8"), String::from("f6"))));
+                for (i, square) in board.squares.iter().enumerate() {
+                    let piece = match square.piece {
+                        Some(p) if p.color == Color::White => p,
+                        _ => continue,
+                    };
+                    let pseudo_legal_moves = piece.get_pseudo_legal_moves(i, &board);
+                    let captures = piece.get_pseudo_legal_captures(i, &board);
+                    let quiet_moves = piece.get_pseudo_legal_quiet_moves(i, &board);
+
+                    assert!(quiet_moves.iter().all(|quiet_move| board.get_piece_at(quiet_move.to).is_none()));
+                    assert_eq!(captures.len() + quiet_moves.len(), pseudo_legal_moves.len());
+                    assert!(pseudo_legal_moves.iter().all(|pseudo_legal_move| captures.contains(pseudo_legal_move) || quiet_moves.contains(pseudo_legal_move)));
+                }
+            }
+        }
+
         mod is_check {
             use super::*;
 
@@ -474,10 +645,38 @@ mod tests {
                 board.make_move(Move::from_chess_move((String::from("e2"), String::from("e4"))));
                 board.make_move(Move::from_chess_move((String::from("f7"), String::from("f5"))));
                 board.make_move(Move::from_chess_move((String::from("d1"), String::from("h5"))));
-                assert_eq!(is_check(board.clone()), true);
+                assert!(is_check(board.clone()));
                 board.make_move(Move::from_chess_move((String::from("g7"), String::from("g6"))));
-                assert_eq!(is_check(board.clone()), false);
+                assert!(!is_check(board.clone()));
             }
         }
     }
+
+    mod get_relative_rank {
+        use super::*;
+
+        #[test]
+        fn it_counts_ranks_from_the_colors_own_side() {
+            let e2 = helpers::square_to_index(String::from("e2"));
+            assert_eq!(get_relative_rank(e2, Color::White), 2);
+            assert_eq!(get_relative_rank(e2, Color::Black), 7);
+        }
+    }
+
+    mod is_pawn {
+        use super::*;
+
+        #[test]
+        fn it_checks_the_pawn_color_and_ignores_indexes_off_the_board() {
+            let board_string = String::from("00000000000rnbqkbnr00pppppppp00--------00--------00--------00--------00PPPPPPPP00RNBQKBNR00000000000");
+            let board: Board = Board::new(board_string, Color::White);
+            let e2 = helpers::square_to_index(String::from("e2")) as i32;
+
+            assert!(is_pawn(&board, e2, Color::White));
+            assert!(!is_pawn(&board, e2, Color::Black));
+            assert!(!is_pawn(&board, e2 + get_forward_step(Color::White), Color::White));
+            assert!(!is_pawn(&board, -9, Color::White));
+            assert!(has_pawn_on_file(&board, 5, Color::Black));
+        }
+    }
 }
\ No newline at end of file
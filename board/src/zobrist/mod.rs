@@ -0,0 +1,39 @@
+use super::square::{ Color, Piece };
+
+const SEED: u64 = 0x5EED_CAFE_F00D_D00D;
+
+pub const PIECE_KEYS: [[u64; 100]; 12] = generate_piece_keys();
+pub const BLACK_TO_MOVE_KEY: u64 = splitmix64(SEED ^ 0xFFFF_FFFF_FFFF_FFFF).1;
+
+const fn splitmix64(state: u64) -> (u64, u64) {
+    let next_state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    let mut z = next_state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    (next_state, z ^ (z >> 31))
+}
+
+const fn generate_piece_keys() -> [[u64; 100]; 12] {
+    let mut keys = [[0; 100]; 12];
+    let mut state = SEED;
+    let mut piece_index = 0;
+    while piece_index < 12 {
+        let mut board_index = 0;
+        while board_index < 100 {
+            let (next_state, key) = splitmix64(state);
+            state = next_state;
+            keys[piece_index][board_index] = key;
+            board_index += 1;
+        }
+        piece_index += 1;
+    }
+    keys
+}
+
+pub fn get_piece_key(piece: Piece, board_index: usize) -> u64 {
+    let color_offset = match piece.color {
+        Color::White => 0,
+        Color::Black => 6,
+    };
+    PIECE_KEYS[color_offset + piece.piece_type as usize][board_index]
+}
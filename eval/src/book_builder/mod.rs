@@ -0,0 +1,291 @@
+use std::cmp;
+use std::collections::HashMap;
+use board::Board;
+use board::Move;
+use board::helpers;
+use board::square::Color;
+use super::get_all_legal_moves;
+use super::san::move_to_san;
+use super::opening_book::{ OpeningBook, PolyglotKeys, BookEntry, encode_move };
+
+const STARTING_POSITION: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct PgnGame {
+    //From white's perspective: 1 for a win, 0.5 for a draw and 0 for a loss. None for unfinished games
+    pub result: Option<f64>,
+    //Set up position from the FEN tag, if the game did not start from the usual one
+    pub fen: Option<String>,
+    pub moves: Vec<String>,
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct BookBuilderOptions {
+    //Only this many plies of each game make it into the book
+    pub max_plies: u32,
+    //Moves played in fewer games, or scoring less for the side playing them, are left out
+    pub min_games: u32,
+    pub min_score: f64,
+}
+
+impl Default for BookBuilderOptions {
+    fn default() -> BookBuilderOptions {
+        BookBuilderOptions {
+            max_plies: 20,
+            min_games: 2,
+            min_score: 0.0,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Default)]
+struct MoveStats {
+    games: u32,
+    //Two points per win and one per draw for the side playing the move, as Polyglot weights count them
+    points: u32,
+}
+
+pub struct BookBuilder {
+    keys: PolyglotKeys,
+    options: BookBuilderOptions,
+    move_stats: HashMap<(u64, u16), MoveStats>,
+}
+
+impl BookBuilder {
+    pub fn new(keys: PolyglotKeys, options: BookBuilderOptions) -> BookBuilder {
+        BookBuilder { keys, options, move_stats: HashMap::new() }
+    }
+
+    //Returns the number of games that counted towards the book
+    pub fn add_pgn(&mut self, text: &str) -> u32 {
+        read_pgn(text).iter()
+            .filter(|game| self.add_game(game))
+            .count() as u32
+    }
+
+    //Unfinished games and games from positions that cannot be read are skipped. A game is followed only up to
+    //its first move the board cannot play, which includes castling, en passant and promotions
+    pub fn add_game(&mut self, game: &PgnGame) -> bool {
+        let result = match game.result {
+            Some(result) => result,
+            None => return false,
+        };
+        let mut board = match Board::from_fen(game.fen.as_deref().unwrap_or(STARTING_POSITION)) {
+            Some(board) => board,
+            None => return false,
+        };
+        for san in game.moves.iter().take(self.options.max_plies as usize) {
+            let chess_move = match find_move(&board, san) {
+                Some(chess_move) => chess_move,
+                None => break,
+            };
+            let score = match board.current_turn.color {
+                Color::White => result,
+                Color::Black => 1.0 - result,
+            };
+            let move_stats = self.move_stats.entry((self.keys.get_hash(&board), encode_move(chess_move))).or_default();
+            move_stats.games += 1;
+            move_stats.points += (score * 2.0) as u32;
+            board.make_move(chess_move);
+        }
+        true
+    }
+
+    //Moves that never scored get no weight and are left out along with those failing the filters.
+    //Within a position, the most successful moves come first
+    pub fn build(self) -> OpeningBook {
+        let options = self.options;
+        let mut entries: Vec<BookEntry> = self.move_stats.iter()
+            .filter(|(_, move_stats)| move_stats.games >= options.min_games && move_stats.points > 0)
+            .filter(|(_, move_stats)| move_stats.points as f64 / (2.0 * move_stats.games as f64) >= options.min_score)
+            .map(|((key, chess_move), move_stats)| BookEntry {
+                key: *key,
+                chess_move: *chess_move,
+                weight: cmp::min(move_stats.points, u16::MAX as u32) as u16,
+                learn: 0,
+            })
+            .collect();
+        entries.sort_by_key(|entry| (entry.key, cmp::Reverse(entry.weight), entry.chess_move));
+        OpeningBook::new(self.keys, entries)
+    }
+}
+
+//Reads the tags and main line of every game, skipping comments, variations and annotation glyphs
+pub fn read_pgn(text: &str) -> Vec<PgnGame> {
+    let mut games = Vec::new();
+    let mut game = PgnGame { result: None, fen: None, moves: Vec::new() };
+    let mut has_movetext = false;
+    let mut variation_depth = 0;
+    let mut token = String::new();
+    //The final newline makes sure the last token is read
+    let mut chars = text.chars().chain(Some('\n'));
+
+    while let Some(c) = chars.next() {
+        let is_token_end = c.is_whitespace() || "{;()[".contains(c);
+        if is_token_end && !token.is_empty() {
+            if variation_depth == 0 {
+                has_movetext = true;
+                if read_movetext_token(&mut game, &token) {
+                    games.push(game);
+                    game = PgnGame { result: None, fen: None, moves: Vec::new() };
+                    has_movetext = false;
+                }
+            }
+            token.clear();
+        }
+        match c {
+            '{' => { chars.by_ref().find(|c| *c == '}'); },
+            ';' => { chars.by_ref().find(|c| *c == '\n'); },
+            '(' => variation_depth += 1,
+            ')' => variation_depth = cmp::max(variation_depth - 1, 0),
+            '[' if variation_depth == 0 => {
+                //A tag after the movetext starts the next game, even if the previous one had no result token
+                if has_movetext {
+                    games.push(game);
+                    game = PgnGame { result: None, fen: None, moves: Vec::new() };
+                    has_movetext = false;
+                }
+                let tag: String = chars.by_ref().take_while(|c| *c != ']').collect();
+                read_tag(&mut game, &tag);
+            },
+            _ if c.is_whitespace() => {},
+            _ => token.push(c),
+        }
+    }
+    if has_movetext {
+        games.push(game);
+    }
+    games
+}
+
+fn read_tag(game: &mut PgnGame, tag: &str) {
+    let mut parts = tag.trim().splitn(2, char::is_whitespace);
+    let name = parts.next().unwrap_or("");
+    let value = parts.next().unwrap_or("").trim().trim_matches('"');
+    match name {
+        "Result" => game.result = get_result(value),
+        "FEN" => game.fen = Some(String::from(value)),
+        _ => {},
+    }
+}
+
+//Returns whether the token ends the game
+fn read_movetext_token(game: &mut PgnGame, token: &str) -> bool {
+    if ["1-0", "0-1", "1/2-1/2", "*"].contains(&token) {
+        game.result = get_result(token);
+        return true;
+    }
+    //Move numbers may be written apart from the move or right before it, as in 1.e4 or 1...e5
+    let san = token.trim_start_matches(|c: char| c.is_ascii_digit()).trim_start_matches('.');
+    if !san.is_empty() && !san.starts_with('$') {
+        game.moves.push(String::from(san));
+    }
+    false
+}
+
+fn get_result(result: &str) -> Option<f64> {
+    match result {
+        "1-0" => Some(1.0),
+        "0-1" => Some(0.0),
+        "1/2-1/2" => Some(0.5),
+        _ => None,
+    }
+}
+
+//Check marks and annotations such as ! and ? are ignored when comparing moves
+fn find_move(board: &Board, san: &str) -> Option<Move> {
+    let strip = |san: &str| String::from(san.trim_end_matches(|c| "+#!?".contains(c)));
+    let san = strip(san);
+    get_all_legal_moves(board).into_iter()
+        .filter(|chess_move| san.ends_with(&helpers::index_to_square(chess_move.to)))
+        .find(|chess_move| strip(&move_to_san(board, chess_move)) == san)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PGN: &str = "\
+        [Event \"Test\"]\n\
+        [Result \"1-0\"]\n\
+        \n\
+        1. e4 {The king's pawn} e5 2. Nf3 (2. f4 exf4) Nc6 $1 3. Bb5 a6 1-0\n\
+        \n\
+        [Result \"1/2-1/2\"]\n\
+        1.e4 e5 2.Nf3 Nf6 ; Petrov\n\
+        3.Nxe5 d6 1/2-1/2\n\
+        \n\
+        [Result \"0-1\"]\n\
+        1. d4 d5 2. c4 0-1\n\
+        \n\
+        [Result \"*\"]\n\
+        1. c4 *\n";
+
+    fn get_starting_board() -> Board {
+        Board::from_fen(STARTING_POSITION).unwrap()
+    }
+
+    mod read_pgn {
+        use super::*;
+
+        #[test]
+        fn it_reads_the_main_line_and_result_of_every_game() {
+            let games = read_pgn(PGN);
+
+            assert_eq!(games.len(), 4);
+            assert_eq!(games[0].moves, vec!["e4", "e5", "Nf3", "Nc6", "Bb5", "a6"]);
+            assert_eq!(games[0].result, Some(1.0));
+            assert_eq!(games[1].moves, vec!["e4", "e5", "Nf3", "Nf6", "Nxe5", "d6"]);
+            assert_eq!(games[1].result, Some(0.5));
+            assert_eq!(games[2].result, Some(0.0));
+            assert_eq!(games[3].result, None);
+        }
+
+        #[test]
+        fn it_reads_set_up_positions() {
+            let games = read_pgn("[FEN \"4k3/8/8/8/8/8/4P3/4K3 w - - 0 1\"]\n\n1. e4 Kd7 1/2-1/2");
+            assert_eq!(games[0].fen, Some(String::from("4k3/8/8/8/8/8/4P3/4K3 w - - 0 1")));
+            assert_eq!(games[0].moves, vec!["e4", "Kd7"]);
+        }
+    }
+
+    mod book_builder {
+        use super::*;
+
+        #[test]
+        fn it_counts_finished_games_and_filters_rare_moves() {
+            let mut builder = BookBuilder::new(PolyglotKeys::default(), BookBuilderOptions::default());
+            assert_eq!(builder.add_pgn(PGN), 3);
+            let book = builder.build();
+            let board = get_starting_board();
+            let e4 = Move::from_chess_move((String::from("e2"), String::from("e4")));
+
+            //1. e4 scored a win and a draw, while 1. d4 was only played once
+            assert_eq!(book.get_moves(&board), vec![(e4, 3)]);
+            //Keyed like any other Polyglot book
+            assert_eq!(book.entries().iter().filter(|entry| entry.key == 0x463B_9618_1691_FC9C).count(), 1);
+            let board = board.test_move(e4);
+            let e5 = Move::from_chess_move((String::from("e7"), String::from("e5")));
+            assert_eq!(book.get_moves(&board), vec![(e5, 1)]);
+        }
+
+        #[test]
+        fn it_filters_by_score_and_depth() {
+            let options = BookBuilderOptions { max_plies: 1, min_games: 1, min_score: 0.5 };
+            let mut builder = BookBuilder::new(PolyglotKeys::default(), options);
+            builder.add_pgn(PGN);
+            let book = builder.build();
+
+            //1. d4 lost its only game, and nothing past the first ply is kept
+            assert_eq!(book.entries().len(), 1);
+            assert_eq!(book.get_moves(&get_starting_board()).len(), 1);
+        }
+
+        #[test]
+        fn it_stops_at_moves_the_board_cannot_play() {
+            let mut builder = BookBuilder::new(PolyglotKeys::default(), BookBuilderOptions { min_games: 1, ..BookBuilderOptions::default() });
+            builder.add_pgn("1. e4 e5 2. Nf3 Nc6 3. Bc4 Bc5 4. O-O Nf6 5. d3 1/2-1/2");
+            assert_eq!(builder.build().entries().len(), 6);
+        }
+    }
+}
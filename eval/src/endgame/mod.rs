@@ -0,0 +1,229 @@
+use std::cmp;
+use board::Board;
+use board::PieceType;
+use board::square::Color;
+use super::eval_params::EvalParams;
+
+//Added for a lone king against enough material to mate, so that trading down into such an ending always pays
+const KNOWN_WIN_VALUE: i32 = 1000;
+//Bonuses for the lone king being far from the centre along each axis, and for the kings being close together
+const PUSH_TO_EDGE_WEIGHT: i32 = 20;
+const PUSH_CLOSE_WEIGHT: i32 = 20;
+//Bishop and knight can only mate in a corner the bishop controls
+const PUSH_TO_CORNER_WEIGHT: i32 = 40;
+
+//Scale factors out of NORMAL_SCALE for endings the stronger side will struggle to win
+const NORMAL_SCALE: i32 = 64;
+const OPPOSITE_BISHOPS_SCALE: i32 = 32;
+const DRAWISH_SCALE: i32 = 8;
+
+#[derive(Default)]
+struct Material {
+    pawns: u32,
+    knights: u32,
+    light_bishops: u32,
+    dark_bishops: u32,
+    rooks: u32,
+    queens: u32,
+    king_index: Option<usize>,
+}
+
+impl Material {
+    fn of_color(board: &Board, color: Color) -> Material {
+        let mut material = Material::default();
+        for (board_index, square) in board.squares.iter().enumerate() {
+            let piece = match square.piece {
+                Some(p) if p.color == color => p,
+                _ => continue,
+            };
+            match piece.piece_type {
+                PieceType::Pawn => material.pawns += 1,
+                PieceType::Knight => material.knights += 1,
+                PieceType::Bishop if is_dark_square(board_index) => material.dark_bishops += 1,
+                PieceType::Bishop => material.light_bishops += 1,
+                PieceType::Rook => material.rooks += 1,
+                PieceType::Queen => material.queens += 1,
+                PieceType::King => material.king_index = Some(board_index),
+            }
+        }
+        material
+    }
+
+    fn bishops(&self) -> u32 {
+        self.light_bishops + self.dark_bishops
+    }
+
+    fn piece_value(&self, params: &EvalParams) -> i32 {
+        (self.knights * params.knight.value + self.bishops() * params.bishop.value
+            + self.rooks * params.rook.value + self.queens * params.queen.value) as i32
+    }
+
+    fn is_lone_king(&self) -> bool {
+        self.pawns + self.knights + self.bishops() + self.rooks + self.queens == 0
+    }
+
+    //Without pawns to promote, enough to force mate against a lone king
+    fn can_mate(&self) -> bool {
+        self.queens > 0 || self.rooks > 0
+            || (self.light_bishops > 0 && self.dark_bishops > 0)
+            || (self.bishops() > 0 && self.knights > 0)
+            || self.knights >= 3
+    }
+}
+
+//From white's perspective, taking the regular evaluation and returning it adjusted for the endings the
+//material on the board is known to lead to
+pub fn evaluate_endgame(board: &Board, params: &EvalParams, value: i32) -> i32 {
+    let white = Material::of_color(board, Color::White);
+    let black = Material::of_color(board, Color::Black);
+    let (white_king, black_king) = match (white.king_index, black.king_index) {
+        (Some(white_king), Some(black_king)) => (white_king, black_king),
+        _ => return value,
+    };
+
+    if black.is_lone_king() && white.pawns == 0 {
+        return evaluate_lone_king(&white, params, white_king, black_king);
+    }
+    if white.is_lone_king() && black.pawns == 0 {
+        return -evaluate_lone_king(&black, params, black_king, white_king);
+    }
+
+    let scale = match value > 0 {
+        true => get_scale(&white, &black, params),
+        false => get_scale(&black, &white, params),
+    };
+    value * scale / NORMAL_SCALE
+}
+
+//From the stronger side's perspective. Material that cannot mate is a draw, whatever it is worth
+fn evaluate_lone_king(strong: &Material, params: &EvalParams, strong_king: usize, weak_king: usize) -> i32 {
+    if !strong.can_mate() {
+        return 0;
+    }
+    let (file, rank) = get_file_and_rank(weak_king);
+    let edge_distance = get_distance_from_centre(file) + get_distance_from_centre(rank);
+    let mut value = KNOWN_WIN_VALUE + strong.piece_value(params)
+        + PUSH_TO_EDGE_WEIGHT * edge_distance
+        + PUSH_CLOSE_WEIGHT * (7 - get_distance(strong_king, weak_king));
+
+    //With a bishop and a knight only, any other corner is a long way from mate
+    if strong.bishops() == 1 && strong.knights == 1 && strong.rooks == 0 && strong.queens == 0 {
+        let corners = match strong.dark_bishops > 0 {
+            true => [81, 18],
+            false => [11, 88],
+        };
+        let corner_distance = corners.iter().map(|corner| get_distance(weak_king, *corner)).min().unwrap_or(0);
+        value += PUSH_TO_CORNER_WEIGHT * (7 - corner_distance);
+    }
+    value
+}
+
+//How much of the stronger side's advantage it can expect to turn into a win
+fn get_scale(strong: &Material, weak: &Material, params: &EvalParams) -> i32 {
+    //Without pawns, being a minor piece up at most is rarely enough, and a minor piece alone never is
+    if strong.pawns == 0 && strong.piece_value(params) - weak.piece_value(params) <= params.bishop.value as i32 {
+        return match strong.piece_value(params) < params.rook.value as i32 {
+            true => 0,
+            false => DRAWISH_SCALE,
+        };
+    }
+
+    //Bishops of opposite colours as the only pieces leave the defender a blockade the attacker cannot break
+    let has_only_a_bishop = |material: &Material| material.bishops() == 1 && material.knights + material.rooks + material.queens == 0;
+    if has_only_a_bishop(strong) && has_only_a_bishop(weak) && strong.light_bishops != weak.light_bishops {
+        return OPPOSITE_BISHOPS_SCALE;
+    }
+    NORMAL_SCALE
+}
+
+fn is_dark_square(board_index: usize) -> bool {
+    (board_index % 10 + board_index / 10) % 2 == 1
+}
+
+//Both from 0 to 7
+fn get_file_and_rank(board_index: usize) -> (i32, i32) {
+    ((board_index % 10) as i32 - 1, 8 - (board_index / 10) as i32)
+}
+
+//0 on the four central files or ranks, up to 3 on the edge
+fn get_distance_from_centre(coordinate: i32) -> i32 {
+    cmp::max(3 - coordinate, coordinate - 4)
+}
+
+//In king moves
+fn get_distance(from: usize, to: usize) -> i32 {
+    let (from_file, from_rank) = get_file_and_rank(from);
+    let (to_file, to_rank) = get_file_and_rank(to);
+    cmp::max((from_file - to_file).abs(), (from_rank - to_rank).abs())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::evaluate;
+    use super::super::eval_params::DEFAULT_EVAL_PARAMS;
+
+    fn evaluate_fen(fen: &str) -> i32 {
+        evaluate(&Board::from_fen(fen).unwrap())
+    }
+
+    mod evaluate_endgame {
+        use super::*;
+
+        #[test]
+        fn it_drives_the_lone_king_to_the_edge() {
+            //The lone king stays three moves away from the other king each time
+            let centre = evaluate_fen("8/1R6/8/8/4k3/8/8/4K3 w - - 0 1");
+            let edge = evaluate_fen("8/1R6/8/8/7k/8/8/4K3 w - - 0 1");
+            let corner = evaluate_fen("8/1R6/8/8/8/8/8/4K2k w - - 0 1");
+
+            assert!(centre > KNOWN_WIN_VALUE);
+            assert!(edge > centre);
+            assert!(corner > edge);
+        }
+
+        #[test]
+        fn it_brings_the_strong_king_closer() {
+            let far = evaluate_fen("7k/8/8/8/8/8/8/K6Q b - - 0 1");
+            let close = evaluate_fen("7k/8/5K2/8/8/8/8/7Q b - - 0 1");
+            assert!(close < far);
+            assert!(far < -KNOWN_WIN_VALUE);
+        }
+
+        #[test]
+        fn it_prefers_the_corner_of_the_bishops_colour() {
+            //The bishop on c1 controls the dark corners a1 and h8
+            let right_corner = evaluate_fen("7k/8/8/8/8/8/8/2B1K1N1 w - - 0 1");
+            let wrong_corner = evaluate_fen("k7/8/8/8/8/8/8/2B1K1N1 w - - 0 1");
+            assert!(right_corner > wrong_corner);
+        }
+
+        #[test]
+        fn it_values_the_strong_side_with_the_given_params() {
+            let board = Board::from_fen("8/1R6/8/8/4k3/8/8/4K3 w - - 0 1").unwrap();
+            let mut params = EvalParams::default();
+            params.rook.value += 100;
+            assert_eq!(evaluate_endgame(&board, &params, 0), evaluate_endgame(&board, &DEFAULT_EVAL_PARAMS, 0) + 100);
+        }
+
+        #[test]
+        fn it_knows_material_that_cannot_mate() {
+            assert_eq!(evaluate_fen("8/8/8/4k3/8/8/8/1N2K1N1 w - - 0 1"), 0);
+            assert_eq!(evaluate_fen("8/8/8/4k3/8/8/8/2B1K3 b - - 0 1"), 0);
+            assert_eq!(evaluate_fen("8/8/8/4k3/8/8/8/4K3 w - - 0 1"), 0);
+            //A minor piece against pawns cannot win either
+            assert_eq!(evaluate_fen("8/5p2/8/4k3/8/8/8/2B1K3 w - - 0 1"), 0);
+        }
+
+        #[test]
+        fn it_scales_down_drawish_endings() {
+            let board = Board::from_fen("8/8/8/4k3/8/3b4/8/R3K3 w - - 0 1").unwrap();
+            assert_eq!(evaluate_endgame(&board, &DEFAULT_EVAL_PARAMS, 160), 160 * DRAWISH_SCALE / NORMAL_SCALE);
+
+            let opposite_bishops = Board::from_fen("8/5p2/4kb2/8/8/2PB4/1P3P2/4K3 w - - 0 1").unwrap();
+            assert_eq!(evaluate_endgame(&opposite_bishops, &DEFAULT_EVAL_PARAMS, 100), 100 * OPPOSITE_BISHOPS_SCALE / NORMAL_SCALE);
+            let same_bishops = Board::from_fen("8/5p2/4k3/8/2b5/2PB4/1P3P2/4K3 w - - 0 1").unwrap();
+            assert_eq!(evaluate_endgame(&same_bishops, &DEFAULT_EVAL_PARAMS, 100), 100);
+        }
+    }
+}
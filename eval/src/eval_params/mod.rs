@@ -0,0 +1,456 @@
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+use board::PieceType;
+use board::helpers;
+use board::square::Color;
+use super::*;
+
+const PIECE_NAMES: [&str; 6] = ["pawn", "knight", "bishop", "rook", "queen", "king"];
+const TABLE_NAMES: [&str; 2] = ["midgame", "endgame"];
+
+//Tables are seen from white's side, and black's values come from the square with the same file on the
+//opposite rank, so that both colors are always valued alike
+#[derive(Clone, Debug, PartialEq)]
+pub struct PieceParams {
+    pub value: u32,
+    pub midgame: [u32; 100],
+    pub endgame: [u32; 100],
+}
+
+//Piece values and piece-square tables of the classical evaluation
+#[derive(Clone, Debug, PartialEq)]
+pub struct EvalParams {
+    pub pawn: PieceParams,
+    pub knight: PieceParams,
+    pub bishop: PieceParams,
+    pub rook: PieceParams,
+    pub queen: PieceParams,
+    pub king: PieceParams,
+}
+
+pub const DEFAULT_EVAL_PARAMS: EvalParams = EvalParams {
+    pawn: PieceParams {
+        value: PAWN_VALUE,
+        midgame: PAWN_MIDGAME_POSITIONAL_VALUE,
+        endgame: PAWN_ENDGAME_POSITIONAL_VALUE,
+    },
+    knight: PieceParams {
+        value: KNIGHT_VALUE,
+        midgame: KNIGHT_MIDGAME_POSITIONAL_VALUE,
+        endgame: KNIGHT_ENDGAME_POSITIONAL_VALUE,
+    },
+    bishop: PieceParams {
+        value: BISHOP_VALUE,
+        midgame: BISHOP_MIDGAME_POSITIONAL_VALUE,
+        endgame: BISHOP_ENDGAME_POSITIONAL_VALUE,
+    },
+    rook: PieceParams {
+        value: ROOK_VALUE,
+        midgame: ROOK_MIDGAME_POSITIONAL_VALUE,
+        endgame: ROOK_ENDGAME_POSITIONAL_VALUE,
+    },
+    queen: PieceParams {
+        value: QUEEN_VALUE,
+        midgame: QUEEN_MIDGAME_POSITIONAL_VALUE,
+        endgame: QUEEN_ENDGAME_POSITIONAL_VALUE,
+    },
+    king: PieceParams {
+        value: KING_VALUE,
+        midgame: KING_MIDGAME_POSITIONAL_VALUE,
+        endgame: KING_ENDGAME_POSITIONAL_VALUE,
+    },
+};
+
+#[derive(Debug)]
+pub enum EvalParamsError {
+    Io(io::Error),
+    Parse(String),
+}
+
+impl fmt::Display for EvalParamsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            EvalParamsError::Io(error) => write!(f, "Could not read the evaluation parameters: {}", error),
+            EvalParamsError::Parse(message) => write!(f, "Invalid evaluation parameters: {}", message),
+        }
+    }
+}
+
+impl From<io::Error> for EvalParamsError {
+    fn from(error: io::Error) -> EvalParamsError {
+        EvalParamsError::Io(error)
+    }
+}
+
+impl Default for EvalParams {
+    fn default() -> EvalParams {
+        DEFAULT_EVAL_PARAMS
+    }
+}
+
+impl PieceParams {
+    pub fn get_square_value(&self, color: Color, is_midgame: bool, board_index: usize) -> u32 {
+        let table = match is_midgame {
+            true => &self.midgame,
+            false => &self.endgame,
+        };
+        match color {
+            Color::White => table[board_index],
+            Color::Black => table[helpers::mirror_index(board_index)],
+        }
+    }
+
+    fn get_tables_mut(&mut self) -> [&mut [u32; 100]; 2] {
+        [&mut self.midgame, &mut self.endgame]
+    }
+}
+
+impl EvalParams {
+    pub fn get_piece_params(&self, piece_type: PieceType) -> &PieceParams {
+        match piece_type {
+            PieceType::Pawn => &self.pawn,
+            PieceType::Knight => &self.knight,
+            PieceType::Bishop => &self.bishop,
+            PieceType::Rook => &self.rook,
+            PieceType::Queen => &self.queen,
+            PieceType::King => &self.king,
+        }
+    }
+
+    pub fn get_piece_params_mut(&mut self, piece_type: PieceType) -> &mut PieceParams {
+        match piece_type {
+            PieceType::Pawn => &mut self.pawn,
+            PieceType::Knight => &mut self.knight,
+            PieceType::Bishop => &mut self.bishop,
+            PieceType::Rook => &mut self.rook,
+            PieceType::Queen => &mut self.queen,
+            PieceType::King => &mut self.king,
+        }
+    }
+
+    fn get_all_piece_params_mut(&mut self) -> [&mut PieceParams; 6] {
+        [&mut self.pawn, &mut self.knight, &mut self.bishop, &mut self.rook, &mut self.queen, &mut self.king]
+    }
+
+    pub fn load(path: &Path) -> Result<EvalParams, EvalParamsError> {
+        EvalParams::from_json(&fs::read_to_string(path)?)
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        fs::write(path, self.to_json())
+    }
+
+    //Anything the file leaves out keeps its built-in default, so a file may hold only the values being tried out
+    pub fn from_json(text: &str) -> Result<EvalParams, EvalParamsError> {
+        let mut params = EvalParams::default();
+        let pieces = json::parse(text)?.into_object("the parameters")?;
+
+        for (piece_name, piece_value) in pieces {
+            let piece_index = PIECE_NAMES.iter().position(|name| *name == piece_name)
+                .ok_or_else(|| EvalParamsError::Parse(format!("Unknown piece \"{}\"", piece_name)))?;
+            let piece_params = &mut params.get_all_piece_params_mut()[piece_index];
+
+            for (key, value) in piece_value.into_object(&piece_name)? {
+                let context = format!("{}.{}", piece_name, key);
+                if key == "value" {
+                    piece_params.value = value.into_u32(&context)?;
+                    continue;
+                }
+                let table_index = TABLE_NAMES.iter().position(|name| *name == key)
+                    .ok_or_else(|| EvalParamsError::Parse(format!("Unknown key \"{}\"", context)))?;
+                let entries = value.into_array(&context)?;
+                if entries.len() != 100 {
+                    return Err(EvalParamsError::Parse(format!("{} must have 100 entries, not {}", context, entries.len())));
+                }
+                let table = &mut piece_params.get_tables_mut()[table_index];
+                for (board_index, entry) in entries.into_iter().enumerate() {
+                    table[board_index] = entry.into_u32(&context)?;
+                }
+            }
+        }
+        Ok(params)
+    }
+
+    //Tables are written out as ten rows of ten, laid out like the board
+    pub fn to_json(&self) -> String {
+        let pieces: Vec<String> = PIECE_NAMES.iter()
+            .zip([&self.pawn, &self.knight, &self.bishop, &self.rook, &self.queen, &self.king].iter())
+            .map(|(piece_name, piece_params)| {
+                let tables: Vec<String> = TABLE_NAMES.iter()
+                    .zip([&piece_params.midgame, &piece_params.endgame].iter())
+                    .map(|(table_name, table)| {
+                        let rows: Vec<String> = table.chunks(10)
+                            .map(|row| format!("      {}", row.iter().map(|entry| entry.to_string()).collect::<Vec<String>>().join(", ")))
+                            .collect();
+                        format!("    \"{}\": [\n{}\n    ]", table_name, rows.join(",\n"))
+                    })
+                    .collect();
+                format!("  \"{}\": {{\n    \"value\": {},\n{}\n  }}", piece_name, piece_params.value, tables.join(",\n"))
+            })
+            .collect();
+        format!("{{\n{}\n}}\n", pieces.join(",\n"))
+    }
+}
+
+//Just enough JSON for the parameter files: objects, arrays and integers
+mod json {
+    use super::EvalParamsError;
+
+    //Parameter files nest three levels deep, anything much deeper is not one of them and would
+    //otherwise recurse until the stack overflows
+    pub const MAX_DEPTH: usize = 32;
+
+    pub enum JsonValue {
+        Number(i64),
+        Array(Vec<JsonValue>),
+        Object(Vec<(String, JsonValue)>),
+    }
+
+    impl JsonValue {
+        pub fn into_object(self, context: &str) -> Result<Vec<(String, JsonValue)>, EvalParamsError> {
+            match self {
+                JsonValue::Object(members) => Ok(members),
+                _ => Err(EvalParamsError::Parse(format!("{} must be an object", context))),
+            }
+        }
+
+        pub fn into_array(self, context: &str) -> Result<Vec<JsonValue>, EvalParamsError> {
+            match self {
+                JsonValue::Array(elements) => Ok(elements),
+                _ => Err(EvalParamsError::Parse(format!("{} must be an array", context))),
+            }
+        }
+
+        pub fn into_u32(self, context: &str) -> Result<u32, EvalParamsError> {
+            match self {
+                JsonValue::Number(number) if number >= 0 && number <= u32::MAX as i64 => Ok(number as u32),
+                _ => Err(EvalParamsError::Parse(format!("{} must hold non-negative integers", context))),
+            }
+        }
+    }
+
+    pub fn parse(text: &str) -> Result<JsonValue, EvalParamsError> {
+        let mut parser = Parser { chars: text.chars().collect(), position: 0, depth: 0 };
+        let value = parser.parse_value()?;
+        parser.skip_whitespace();
+        match parser.position == parser.chars.len() {
+            true => Ok(value),
+            false => Err(parser.error("Unexpected text after the end")),
+        }
+    }
+
+    struct Parser {
+        chars: Vec<char>,
+        position: usize,
+        //Objects and arrays the parser is inside of
+        depth: usize,
+    }
+
+    impl Parser {
+        fn parse_value(&mut self) -> Result<JsonValue, EvalParamsError> {
+            self.skip_whitespace();
+            match self.peek() {
+                Some('{') | Some('[') if self.depth == MAX_DEPTH => Err(self.error("Nested too deeply")),
+                Some('{') => self.parse_nested(Parser::parse_object),
+                Some('[') => self.parse_nested(Parser::parse_array),
+                Some(c) if c == '-' || c.is_ascii_digit() => self.parse_number(),
+                _ => Err(self.error("Expected a value")),
+            }
+        }
+
+        fn parse_nested(&mut self, parse: fn(&mut Parser) -> Result<JsonValue, EvalParamsError>) -> Result<JsonValue, EvalParamsError> {
+            self.depth += 1;
+            let value = parse(self);
+            self.depth -= 1;
+            value
+        }
+
+        fn parse_object(&mut self) -> Result<JsonValue, EvalParamsError> {
+            self.expect('{')?;
+            let mut members = Vec::new();
+            self.skip_whitespace();
+            if self.peek() == Some('}') {
+                self.position += 1;
+                return Ok(JsonValue::Object(members));
+            }
+            loop {
+                self.skip_whitespace();
+                let key = self.parse_string()?;
+                self.skip_whitespace();
+                self.expect(':')?;
+                members.push((key, self.parse_value()?));
+                self.skip_whitespace();
+                match self.next() {
+                    Some(',') => continue,
+                    Some('}') => return Ok(JsonValue::Object(members)),
+                    _ => return Err(self.error("Expected ',' or '}'")),
+                }
+            }
+        }
+
+        fn parse_array(&mut self) -> Result<JsonValue, EvalParamsError> {
+            self.expect('[')?;
+            let mut elements = Vec::new();
+            self.skip_whitespace();
+            if self.peek() == Some(']') {
+                self.position += 1;
+                return Ok(JsonValue::Array(elements));
+            }
+            loop {
+                elements.push(self.parse_value()?);
+                self.skip_whitespace();
+                match self.next() {
+                    Some(',') => continue,
+                    Some(']') => return Ok(JsonValue::Array(elements)),
+                    _ => return Err(self.error("Expected ',' or ']'")),
+                }
+            }
+        }
+
+        //Escapes other than \" and \\ never show up in parameter names
+        fn parse_string(&mut self) -> Result<String, EvalParamsError> {
+            self.expect('"')?;
+            let mut string = String::new();
+            loop {
+                match self.next() {
+                    Some('"') => return Ok(string),
+                    Some('\\') => match self.next() {
+                        Some(c) if c == '"' || c == '\\' => string.push(c),
+                        _ => return Err(self.error("Unsupported escape sequence")),
+                    },
+                    Some(c) => string.push(c),
+                    None => return Err(self.error("Unterminated string")),
+                }
+            }
+        }
+
+        fn parse_number(&mut self) -> Result<JsonValue, EvalParamsError> {
+            let start = self.position;
+            if self.peek() == Some('-') {
+                self.position += 1;
+            }
+            while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+                self.position += 1;
+            }
+            let number: String = self.chars[start..self.position].iter().collect();
+            number.parse().map(JsonValue::Number).map_err(|_| self.error("Expected an integer"))
+        }
+
+        fn skip_whitespace(&mut self) {
+            while self.peek().is_some_and(|c| c.is_whitespace()) {
+                self.position += 1;
+            }
+        }
+
+        fn expect(&mut self, expected: char) -> Result<(), EvalParamsError> {
+            match self.next() {
+                Some(c) if c == expected => Ok(()),
+                _ => Err(self.error(&format!("Expected '{}'", expected))),
+            }
+        }
+
+        fn peek(&self) -> Option<char> {
+            self.chars.get(self.position).copied()
+        }
+
+        fn next(&mut self) -> Option<char> {
+            let c = self.peek();
+            self.position += 1;
+            c
+        }
+
+        fn error(&self, message: &str) -> EvalParamsError {
+            EvalParamsError::Parse(format!("{} at character {}", message, self.position))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    mod from_json {
+        use super::*;
+
+        #[test]
+        fn it_reads_back_what_it_writes() {
+            let mut params = EvalParams::default();
+            params.knight.value = 325;
+            params.rook.endgame[44] = 17;
+
+            assert_eq!(EvalParams::from_json(&params.to_json()).unwrap(), params);
+        }
+
+        #[test]
+        fn it_keeps_the_defaults_for_anything_left_out() {
+            let params = EvalParams::from_json("{ \"bishop\": { \"value\": 330 } }").unwrap();
+            let mut expected_params = EvalParams::default();
+            expected_params.bishop.value = 330;
+
+            assert_eq!(params, expected_params);
+        }
+
+        #[test]
+        fn it_rejects_unknown_keys_and_bad_tables() {
+            assert!(EvalParams::from_json("{ \"bishops\": { \"value\": 330 } }").is_err());
+            assert!(EvalParams::from_json("{ \"pawn\": { \"midgame\": [1, 2, 3] } }").is_err());
+            assert!(EvalParams::from_json("{ \"pawn\": { \"value\": -100 } }").is_err());
+            assert!(EvalParams::from_json("{ \"pawn\": { \"value\": 100 }").is_err());
+        }
+
+        #[test]
+        fn it_rejects_missing_keys_and_values() {
+            assert!(EvalParams::from_json("").is_err());
+            assert!(EvalParams::from_json("{ : { \"value\": 100 } }").is_err());
+            assert!(EvalParams::from_json("{ \"pawn\" { \"value\": 100 } }").is_err());
+            assert!(EvalParams::from_json("{ \"pawn\": }").is_err());
+            assert!(EvalParams::from_json("{ \"pawn\": { \"value\": } }").is_err());
+            assert!(EvalParams::from_json("{ \"pawn\": { \"value\": 100, } }").is_err());
+            assert!(EvalParams::from_json("{ \"pawn: { \"value\": 100 } }").is_err());
+        }
+
+        #[test]
+        fn it_rejects_values_nested_too_deeply() {
+            let nested = |depth: usize| format!("{}{}", "[".repeat(depth), "]".repeat(depth));
+            let error = EvalParams::from_json(&format!("{{ \"pawn\": {} }}", nested(100_000))).err().unwrap();
+
+            assert!(error.to_string().contains("Nested too deeply"));
+            assert!(json::parse(&nested(json::MAX_DEPTH)).is_ok());
+            assert!(json::parse(&nested(json::MAX_DEPTH + 1)).is_err());
+        }
+
+        #[test]
+        fn it_rejects_tables_of_the_wrong_length() {
+            let table = |length: usize| vec!["0"; length].join(", ");
+            let json = |length: usize| format!("{{ \"knight\": {{ \"endgame\": [{}] }} }}", table(length));
+
+            assert!(EvalParams::from_json(&json(100)).is_ok());
+            assert!(EvalParams::from_json(&json(0)).is_err());
+            assert!(EvalParams::from_json(&json(99)).is_err());
+            assert!(EvalParams::from_json(&json(101)).is_err());
+            assert!(EvalParams::from_json("{ \"knight\": { \"endgame\": [[0]] } }").is_err());
+        }
+
+        #[test]
+        fn it_rejects_values_of_the_wrong_type() {
+            assert!(EvalParams::from_json("[]").is_err());
+            assert!(EvalParams::from_json("{ \"pawn\": 100 }").is_err());
+            assert!(EvalParams::from_json("{ \"pawn\": { \"value\": [100] } }").is_err());
+            assert!(EvalParams::from_json("{ \"pawn\": { \"value\": 100.5 } }").is_err());
+            assert!(EvalParams::from_json("{ \"pawn\": { \"value\": \"100\" } }").is_err());
+            assert!(EvalParams::from_json("{ \"pawn\": { \"value\": 4294967296 } }").is_err());
+            assert!(EvalParams::from_json("{ \"pawn\": { \"midgame\": 0 } }").is_err());
+        }
+
+        #[test]
+        fn it_rejects_trailing_garbage() {
+            assert!(EvalParams::from_json("{ \"pawn\": { \"value\": 100 } }\n").is_ok());
+            assert!(EvalParams::from_json("{ \"pawn\": { \"value\": 100 } } }").is_err());
+            assert!(EvalParams::from_json("{ \"pawn\": { \"value\": 100 } } x").is_err());
+            assert!(EvalParams::from_json("{} {}").is_err());
+            assert!(EvalParams::from_json("{ \"pawn\": { \"value\": 100x } }").is_err());
+        }
+    }
+}
@@ -0,0 +1,132 @@
+use board::Board;
+use board::Move;
+use super::pawn_structure::PawnHashTable;
+use super::eval_params::EvalParams;
+use super::{ PAWN_CACHE_ENTRIES, get_game_phase, get_side_to_move_evaluation };
+
+//Static evaluation used by the search. Every search thread works on its own clone
+pub trait Evaluator: Clone + Send {
+    //From the side to move's perspective
+    fn evaluate(&mut self, board: &Board) -> i32;
+
+    //Called with the positions before and after each move the search plays, None standing for a null
+    //move, so that an evaluator can update its state incrementally instead of starting from scratch
+    fn push_move(&mut self, _board: &Board, _chess_move: Option<Move>, _next_board: &Board) {}
+
+    //Called once the search has taken the last pushed move back
+    fn pop_move(&mut self) {}
+}
+
+//Material, piece-square tables, pawn structure, piece activity and king safety
+#[derive(Clone)]
+pub struct ClassicalEvaluator {
+    params: EvalParams,
+    pawn_table: PawnHashTable,
+}
+
+impl Default for ClassicalEvaluator {
+    fn default() -> ClassicalEvaluator {
+        ClassicalEvaluator::new()
+    }
+}
+
+impl ClassicalEvaluator {
+    pub fn new() -> ClassicalEvaluator {
+        ClassicalEvaluator::with_params(EvalParams::default())
+    }
+
+    pub fn with_params(params: EvalParams) -> ClassicalEvaluator {
+        ClassicalEvaluator { params, pawn_table: PawnHashTable::new(PAWN_CACHE_ENTRIES) }
+    }
+
+    pub fn params(&self) -> &EvalParams {
+        &self.params
+    }
+}
+
+impl Evaluator for ClassicalEvaluator {
+    fn evaluate(&mut self, board: &Board) -> i32 {
+        let phase = get_game_phase(board);
+        let pawn_structure_value = self.pawn_table.evaluate(board, phase);
+        get_side_to_move_evaluation(board, &self.params, pawn_structure_value, phase)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use board::PieceType;
+    use board::square::Color;
+    use super::super::{ Engine, EngineOptions, evaluate };
+
+    //Counts material only and checks that every pushed move is popped again
+    #[derive(Clone)]
+    struct MaterialEvaluator {
+        line_length: usize,
+        pushed_moves: u64,
+    }
+
+    impl Evaluator for MaterialEvaluator {
+        fn evaluate(&mut self, board: &Board) -> i32 {
+            let (white_value, black_value) = board.squares.iter()
+                .filter_map(|square| square.piece)
+                .fold((0, 0), |(white_value, black_value), piece| {
+                    let value = match piece.piece_type {
+                        PieceType::Pawn => 100,
+                        PieceType::Knight | PieceType::Bishop => 300,
+                        PieceType::Rook => 500,
+                        PieceType::Queen => 900,
+                        PieceType::King => 0,
+                    };
+                    match piece.color {
+                        Color::White => (white_value + value, black_value),
+                        Color::Black => (white_value, black_value + value),
+                    }
+                });
+            match board.current_turn.color {
+                Color::White => white_value - black_value,
+                Color::Black => black_value - white_value,
+            }
+        }
+
+        fn push_move(&mut self, _board: &Board, _chess_move: Option<Move>, _next_board: &Board) {
+            self.line_length += 1;
+            self.pushed_moves += 1;
+        }
+
+        fn pop_move(&mut self) {
+            self.line_length -= 1;
+        }
+    }
+
+    mod classical_evaluator {
+        use super::*;
+
+        #[test]
+        fn it_matches_the_uncached_evaluation() {
+            let board_string = String::from("00000000000----k---00-----ppp00--------00---P----00--------00--------00P-P-P---00----K---00000000000");
+            let board: Board = Board::new(board_string, Color::Black);
+            let mut evaluator = ClassicalEvaluator::new();
+
+            assert_eq!(evaluator.evaluate(&board), evaluate(&board));
+            assert_eq!(evaluator.evaluate(&board), evaluate(&board));
+        }
+    }
+
+    mod engine {
+        use super::*;
+
+        #[test]
+        fn it_searches_with_a_custom_evaluator() {
+            let board_string = String::from("00000000000----k---00--------00--------00--------00------n-00--------00--------00---QK---00000000000");
+            let board: Board = Board::new(board_string, Color::White);
+            let evaluator = MaterialEvaluator { line_length: 0, pushed_moves: 0 };
+            let mut engine = Engine::with_evaluator(EngineOptions::default(), evaluator);
+            let search_result = engine.search(&board, 3);
+
+            assert_eq!(search_result.best_move, Some(Move::from_chess_move((String::from("d1"), String::from("g4")))));
+            assert_eq!(engine.evaluator().line_length, 0);
+            assert!(engine.evaluator().pushed_moves > 0);
+        }
+    }
+}
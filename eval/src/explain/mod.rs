@@ -0,0 +1,176 @@
+use std::fmt;
+use board::Board;
+use board::PieceType;
+use board::square::Color;
+use super::eval_params::{ EvalParams, DEFAULT_EVAL_PARAMS };
+use super::{ pawn_structure, piece_activity, king_safety };
+use super::{ evaluate_with_params, get_game_phase, taper };
+
+const PIECE_TYPES: [PieceType; 6] = [PieceType::Pawn, PieceType::Knight, PieceType::Bishop, PieceType::Rook, PieceType::Queen, PieceType::King];
+const PIECE_NAMES: [&str; 6] = ["pawn", "knight", "bishop", "rook", "queen", "king"];
+
+//Every term of the classical evaluation for one side, from that side's own perspective
+#[derive(Clone, Debug, PartialEq, Default)]
+pub struct SideBreakdown {
+    //Piece values without the king's, which both sides always have
+    pub material: i32,
+    //Tapered piece-square values, one per piece type from pawn to king
+    pub piece_square: [i32; 6],
+    pub pawn_structure: i32,
+    pub mobility: i32,
+    //Rooks on open files and the seventh rank, knight outposts and the bishop pair
+    pub piece_placement: i32,
+    pub king_safety: i32,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct EvalBreakdown {
+    //From 0 with only kings and pawns left up to MAX_GAME_PHASE in the starting position
+    pub phase: u32,
+    pub white: SideBreakdown,
+    pub black: SideBreakdown,
+    //From white's perspective, what the knowledge of specific endings adds to the difference between the sides
+    pub endgame: i32,
+    //From white's perspective, equal to white's terms minus black's plus the endgame term whenever both kings
+    //are on the board
+    pub total: i32,
+}
+
+impl SideBreakdown {
+    pub fn total(&self) -> i32 {
+        self.material + self.piece_square.iter().sum::<i32>() + self.pawn_structure + self.mobility + self.piece_placement + self.king_safety
+    }
+
+    fn get_terms(&self) -> Vec<(String, i32)> {
+        let mut terms = vec![(String::from("material"), self.material)];
+        terms.extend(PIECE_NAMES.iter().zip(self.piece_square.iter()).map(|(name, value)| (format!("{}_square", name), *value)));
+        terms.push((String::from("pawn_structure"), self.pawn_structure));
+        terms.push((String::from("mobility"), self.mobility));
+        terms.push((String::from("piece_placement"), self.piece_placement));
+        terms.push((String::from("king_safety"), self.king_safety));
+        terms.push((String::from("total"), self.total()));
+        terms
+    }
+}
+
+impl EvalBreakdown {
+    pub fn to_json(&self) -> String {
+        let side_to_json = |side: &SideBreakdown| side.get_terms().iter()
+            .map(|(name, value)| format!("\"{}\": {}", name, value))
+            .collect::<Vec<String>>()
+            .join(", ");
+        format!("{{\n  \"phase\": {},\n  \"white\": {{ {} }},\n  \"black\": {{ {} }},\n  \"endgame\": {},\n  \"total\": {}\n}}",
+            self.phase, side_to_json(&self.white), side_to_json(&self.black), self.endgame, self.total)
+    }
+}
+
+impl fmt::Display for EvalBreakdown {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{:<16}{:>8}{:>8}{:>8}", "term", "white", "black", "diff")?;
+        for ((name, white_value), (_, black_value)) in self.white.get_terms().iter().zip(self.black.get_terms().iter()) {
+            writeln!(f, "{:<16}{:>8}{:>8}{:>8}", name, white_value, black_value, white_value - black_value)?;
+        }
+        writeln!(f, "endgame {}", self.endgame)?;
+        writeln!(f, "phase {}", self.phase)?;
+        write!(f, "evaluation {} (white's perspective)", self.total)
+    }
+}
+
+pub fn explain(board: &Board) -> EvalBreakdown {
+    explain_with_params(board, &DEFAULT_EVAL_PARAMS)
+}
+
+pub fn explain_with_params(board: &Board, params: &EvalParams) -> EvalBreakdown {
+    let phase = get_game_phase(board);
+    let total = match board.current_turn.color {
+        Color::White => evaluate_with_params(board, params),
+        Color::Black => -evaluate_with_params(board, params),
+    };
+    let white = get_side_breakdown(board, params, Color::White, phase);
+    let black = get_side_breakdown(board, params, Color::Black, phase);
+    let endgame = total - white.total() + black.total();
+    EvalBreakdown { phase, white, black, endgame, total }
+}
+
+fn get_side_breakdown(board: &Board, params: &EvalParams, color: Color, phase: u32) -> SideBreakdown {
+    let mut breakdown = SideBreakdown::default();
+    for (board_index, square) in board.squares.iter().enumerate() {
+        let piece = match square.piece {
+            Some(p) if p.color == color => p,
+            _ => continue,
+        };
+        let piece_params = params.get_piece_params(piece.piece_type);
+        if piece.piece_type != PieceType::King {
+            breakdown.material += piece_params.value as i32;
+        }
+        let piece_index = PIECE_TYPES.iter().position(|piece_type| *piece_type == piece.piece_type).unwrap();
+        let midgame_value = piece_params.get_square_value(color, true, board_index);
+        let endgame_value = piece_params.get_square_value(color, false, board_index);
+        breakdown.piece_square[piece_index] += taper(midgame_value, endgame_value, phase) as i32;
+    }
+    let (mobility, piece_placement) = piece_activity::evaluate_piece_activity_of_color(board, color, phase);
+    breakdown.pawn_structure = pawn_structure::evaluate_pawn_structure_of_color(board, color, phase);
+    breakdown.mobility = mobility;
+    breakdown.piece_placement = piece_placement;
+    breakdown.king_safety = king_safety::evaluate_king_safety_of_color(board, color, phase);
+    breakdown
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::evaluate;
+
+    mod explain {
+        use super::*;
+
+        #[test]
+        fn it_adds_up_to_the_evaluation() {
+            let board = Board::from_fen("r1bqk2r/pp3ppp/2n2n2/3p4/1b1P4/2N2N2/PP2BPPP/R2QKB1R b - - 0 1").unwrap();
+            let breakdown = explain(&board);
+
+            assert_eq!(breakdown.white.total() - breakdown.black.total(), breakdown.total);
+            assert_eq!(breakdown.endgame, 0);
+            assert_eq!(breakdown.total, -evaluate(&board));
+        }
+
+        #[test]
+        fn it_shows_what_the_endgame_knowledge_adds() {
+            let board = Board::from_fen("8/8/8/4k3/8/8/8/1N2K1N1 w - - 0 1").unwrap();
+            let breakdown = explain(&board);
+
+            assert_eq!(breakdown.total, 0);
+            assert_eq!(breakdown.endgame, breakdown.black.total() - breakdown.white.total());
+            assert!(breakdown.to_json().contains("\"endgame\": "));
+        }
+
+        #[test]
+        fn it_splits_the_terms_by_side() {
+            let board = Board::from_fen("4k3/8/8/8/8/8/2PPP3/2Q1K3 w - - 0 1").unwrap();
+            let breakdown = explain(&board);
+
+            assert_eq!(breakdown.phase, 4);
+            assert_eq!(breakdown.white.material, 3 * 100 + 900);
+            assert_eq!(breakdown.black.material, 0);
+            assert_eq!(breakdown.black.piece_square[0], 0);
+            //The queen behind its own pawns has fewer moves than a queen typically has
+            assert!(breakdown.white.mobility < 0);
+            assert_eq!(breakdown.black.mobility, 0);
+            assert_eq!(breakdown.black.pawn_structure, 0);
+        }
+
+        #[test]
+        fn it_prints_every_term() {
+            let board = Board::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w - - 0 1").unwrap();
+            let breakdown = explain(&board);
+            let table = breakdown.to_string();
+            let json = breakdown.to_json();
+
+            for name in ["material", "queen_square", "pawn_structure", "mobility", "piece_placement", "king_safety"].iter() {
+                assert!(table.contains(name));
+                assert!(json.contains(&format!("\"{}\": ", name)));
+            }
+            assert!(json.contains("\"phase\": 24"));
+        }
+    }
+}
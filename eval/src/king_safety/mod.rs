@@ -0,0 +1,194 @@
+use std::cmp;
+use board::Board;
+use board::PieceType;
+use board::square::Color;
+use board::square::{ get_enemy_color, get_relative_rank, has_pawn_on_file, is_pawn };
+use board::square::valid_moves;
+use board::square::valid_moves::is_out_of_bounds;
+use super::MAX_GAME_PHASE;
+
+const KING_STEPS: [i8; 8] = [-1, -11, -10, -9, 1, 11, 10, 9];
+
+//Penalties for the shield pawn on each file around the king, by how far it has moved away from it
+const PAWN_SHIELD_ADVANCED_PENALTY: i32 = 10;
+const PAWN_SHIELD_MISSING_PENALTY: i32 = 25;
+//Penalties for the closest enemy pawn on each file around the king, by how many ranks it is away.
+//One rank away it is stuck in front of the king's own pawns and opens nothing
+const PAWN_STORM_PENALTY: [i32; 8] = [0, 10, 30, 15, 5, 0, 0, 0];
+const SEMI_OPEN_FILE_NEAR_KING_PENALTY: i32 = 10;
+const OPEN_FILE_NEAR_KING_PENALTY: i32 = 25;
+
+//Attack units for each enemy piece reaching a square next to the king
+const KNIGHT_ATTACK_UNITS: i32 = 2;
+const BISHOP_ATTACK_UNITS: i32 = 2;
+const ROOK_ATTACK_UNITS: i32 = 3;
+const QUEEN_ATTACK_UNITS: i32 = 5;
+//A lone attacker is rarely dangerous, while the danger of several grows much faster than their number
+const MIN_KING_ATTACKERS: i32 = 2;
+const KING_DANGER_SCALE: i32 = 2;
+const MAX_KING_DANGER: i32 = 500;
+
+//From white's perspective
+pub fn evaluate_king_safety(board: &Board, phase: u32) -> i32 {
+    evaluate_king_safety_of_color(board, Color::White, phase) - evaluate_king_safety_of_color(board, Color::Black, phase)
+}
+
+//From the color's own perspective. King safety only matters while there is material left to attack with
+pub fn evaluate_king_safety_of_color(board: &Board, color: Color, phase: u32) -> i32 {
+    get_king_safety(board, color) * phase as i32 / MAX_GAME_PHASE as i32
+}
+
+fn get_king_safety(board: &Board, color: Color) -> i32 {
+    let king_index = board.squares.iter().position(|square| match square.piece {
+        Some(p) => p.piece_type == PieceType::King && p.color == color,
+        None => false,
+    });
+    match king_index {
+        Some(king_index) => get_pawn_cover(board, king_index, color) - get_king_danger(board, king_index, color),
+        None => 0,
+    }
+}
+
+//Shield, storm and open file terms for the king's file and both files next to it
+fn get_pawn_cover(board: &Board, king_index: usize, color: Color) -> i32 {
+    let king_rank = get_relative_rank(king_index, color);
+    let enemy_color = get_enemy_color(color);
+    let king_file = (king_index % 10) as i32;
+    let mut value = 0;
+
+    for file in (king_file - 1..=king_file + 1).filter(|file| (1..=8).contains(file)) {
+        let closest_pawn_distance = |pawn_color: Color| (1..=8)
+            .map(|row| row * 10 + file as usize)
+            .filter(|index| is_pawn(board, *index as i32, pawn_color))
+            .map(|index| get_relative_rank(index, color) as i32 - king_rank as i32)
+            .filter(|distance| *distance > 0)
+            .min();
+
+        match closest_pawn_distance(color) {
+            Some(1) => {},
+            Some(2) => value -= PAWN_SHIELD_ADVANCED_PENALTY,
+            _ => value -= PAWN_SHIELD_MISSING_PENALTY,
+        }
+        if let Some(distance) = closest_pawn_distance(enemy_color) {
+            value -= PAWN_STORM_PENALTY[distance as usize];
+        }
+        if !has_pawn_on_file(board, file, color) {
+            value -= match has_pawn_on_file(board, file, enemy_color) {
+                true => SEMI_OPEN_FILE_NEAR_KING_PENALTY,
+                false => OPEN_FILE_NEAR_KING_PENALTY,
+            };
+        }
+    }
+    value
+}
+
+fn get_king_danger(board: &Board, king_index: usize, color: Color) -> i32 {
+    let enemy_color = get_enemy_color(color);
+    let king_zone: Vec<usize> = KING_STEPS.iter()
+        .map(|step| king_index as i8 + step)
+        .filter(|index| !is_out_of_bounds(*index))
+        .map(|index| index as usize)
+        .chain(Some(king_index))
+        .collect();
+    let mut attackers = 0;
+    let mut attack_units = 0;
+
+    for (board_index, square) in board.squares.iter().enumerate() {
+        let piece = match square.piece {
+            Some(p) if p.color == enemy_color => p,
+            _ => continue,
+        };
+        let index = board_index as i8;
+        let (moves, units) = match piece.piece_type {
+            PieceType::Knight => (valid_moves::get_knight_moves(index, board, enemy_color), KNIGHT_ATTACK_UNITS),
+            PieceType::Bishop => (valid_moves::get_bishop_moves(index, board, enemy_color), BISHOP_ATTACK_UNITS),
+            PieceType::Rook => (valid_moves::get_rook_moves(index, board, enemy_color), ROOK_ATTACK_UNITS),
+            PieceType::Queen => (valid_moves::get_queen_moves(index, board, enemy_color), QUEEN_ATTACK_UNITS),
+            PieceType::Pawn | PieceType::King => continue,
+        };
+        if moves.iter().any(|chess_move| king_zone.contains(&chess_move.to)) {
+            attackers += 1;
+            attack_units += units;
+        }
+    }
+
+    match attackers >= MIN_KING_ATTACKERS {
+        true => cmp::min(attack_units * attack_units * KING_DANGER_SCALE, MAX_KING_DANGER),
+        false => 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use board::helpers;
+
+    mod evaluate_king_safety {
+        use super::*;
+
+        #[test]
+        fn it_is_balanced_in_the_starting_position() {
+            let board_string = String::from("00000000000rnbqkbnr00pppppppp00--------00--------00--------00--------00PPPPPPPP00RNBQKBNR00000000000");
+            let board: Board = Board::new(board_string, Color::White);
+            assert_eq!(evaluate_king_safety(&board, MAX_GAME_PHASE), 0);
+        }
+
+        #[test]
+        fn it_is_phased_out_in_the_endgame() {
+            let board_string = String::from("00000000000------k-00-----ppp00--------00--------00--------00--------00--------00------K-00000000000");
+            let board: Board = Board::new(board_string, Color::White);
+            assert!(evaluate_king_safety(&board, MAX_GAME_PHASE) < 0);
+            assert_eq!(evaluate_king_safety(&board, 0), 0);
+        }
+    }
+
+    mod get_pawn_cover {
+        use super::*;
+
+        #[test]
+        fn it_prefers_an_intact_pawn_shield() {
+            let board_string = String::from("00000000000------k-00-----ppp00--------00--------00--------00--------00-----PPP00------K-00000000000");
+            let intact_board: Board = Board::new(board_string, Color::White);
+            let board_string = String::from("00000000000------k-00-----ppp00--------00--------00--------00------P-00-----P-P00------K-00000000000");
+            let advanced_board: Board = Board::new(board_string, Color::White);
+            let g1 = helpers::square_to_index(String::from("g1"));
+
+            assert_eq!(get_pawn_cover(&intact_board, g1, Color::White), 0);
+            assert_eq!(get_pawn_cover(&advanced_board, g1, Color::White), -PAWN_SHIELD_ADVANCED_PENALTY);
+        }
+
+        #[test]
+        fn it_penalizes_open_files_and_storming_pawns() {
+            let board_string = String::from("00000000000------k-00-----p-p00--------00--------00--------00------p-00-----P-P00------K-00000000000");
+            let board: Board = Board::new(board_string, Color::White);
+            let g1 = helpers::square_to_index(String::from("g1"));
+            //The g pawn is gone and black's has stormed up to g3
+            let expected = -PAWN_SHIELD_MISSING_PENALTY - PAWN_STORM_PENALTY[2] - SEMI_OPEN_FILE_NEAR_KING_PENALTY;
+
+            assert_eq!(get_pawn_cover(&board, g1, Color::White), expected);
+        }
+    }
+
+    mod get_king_danger {
+        use super::*;
+
+        #[test]
+        fn it_counts_attack_units_of_several_attackers() {
+            let board_string = String::from("00000000000------k-00-----ppp00--------00--------00-----q--00------n-00-----PPP00------K-00000000000");
+            let board: Board = Board::new(board_string, Color::White);
+            let g1 = helpers::square_to_index(String::from("g1"));
+            let units = QUEEN_ATTACK_UNITS + KNIGHT_ATTACK_UNITS;
+
+            assert_eq!(get_king_danger(&board, g1, Color::White), units * units * KING_DANGER_SCALE);
+        }
+
+        #[test]
+        fn it_ignores_a_lone_attacker() {
+            let board_string = String::from("00000000000------k-00-----ppp00--------00--------00--------00------n-00-----PPP00------K-00000000000");
+            let board: Board = Board::new(board_string, Color::White);
+            let g1 = helpers::square_to_index(String::from("g1"));
+
+            assert_eq!(get_king_danger(&board, g1, Color::White), 0);
+        }
+    }
+}
@@ -1,29 +1,44 @@
 extern crate board;
-use std::collections::HashMap;
-#[macro_use] extern crate itertools;
 use std::cmp;
-use itertools::Itertools;
-use std::rc::Rc;
+use std::fmt;
+use std::mem;
+use std::time::{ Duration, Instant, SystemTime, UNIX_EPOCH };
+use std::sync::Arc;
+use std::sync::atomic::{ AtomicBool, Ordering };
+use std::thread;
 use board::Move;
 use board::Board;
 use board::square::Color;
 use board::Piece;
 use board::PieceType;
+use board::square::is_in_check;
 
-const BLACK_PAWN_POSITIONAL_VALUE: [u32; 100] = [
-    0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-    0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-    0, 20, 20, 20, 20, 20, 50, 50, 50, 0,
-    0, 30, 30, 40, 40, 40, 20, 40, 40, 0,
-    0, 10, 10, 50, 50, 50, 10, 30, 30, 0,
-    0, 20, 20, 40, 40, 40, 20, 20, 20, 0,
-    0, 20, 20, 30, 30, 30, 20, 20, 20, 0,
-    0, 20, 20, 20, 20, 20, 20, 20, 20, 0,
-    0, 100, 100, 100, 100, 100, 100, 100, 100, 0,
-    0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-];
+pub mod san;
+pub mod transposition_table;
+pub mod move_ordering;
+pub mod see;
+pub mod pawn_structure;
+pub mod piece_activity;
+pub mod king_safety;
+pub mod evaluator;
+pub mod eval_params;
+pub mod tuning;
+pub mod nnue;
+pub mod explain;
+pub mod opening_book;
+pub mod book_builder;
+pub mod tablebase;
+pub mod syzygy;
+pub mod endgame;
+use transposition_table::{ TranspositionTable, CacheEntry, Bound };
+use move_ordering::{ MovePicker, MoveHistory };
+use evaluator::{ Evaluator, ClassicalEvaluator };
+use eval_params::{ EvalParams, DEFAULT_EVAL_PARAMS };
+use opening_book::{ OpeningBook, BookSelection, RandomGenerator };
+use tablebase::{ Tablebase, Wdl };
 
-const WHITE_PAWN_POSITIONAL_VALUE: [u32; 100] = [
+//Piece-square tables as seen from white's side. Black uses the same tables with the ranks flipped
+const PAWN_MIDGAME_POSITIONAL_VALUE: [u32; 100] = [
     0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
     0, 100, 100, 100, 100, 100, 100, 100, 100, 0,
     0, 20, 20, 20, 20, 20, 20, 20, 20, 0,
@@ -36,20 +51,7 @@ const WHITE_PAWN_POSITIONAL_VALUE: [u32; 100] = [
     0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
 ];
 
-const BLACK_KNIGHT_POSITIONAL_VALUE: [u32; 100] = [
-    0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-    0, 0, 30, 10, 10, 10, 10, 30, 0, 0,
-    0, 10, 20, 20, 30, 30, 20, 20, 10, 0,
-    0, 10, 20, 40, 40, 40, 40, 20, 10, 0,
-    0, 20, 30, 50, 50, 50, 50, 30, 20, 0,
-    0, 20, 30, 50, 50, 50, 50, 30, 20, 0,
-    0, 10, 20, 40, 40, 40, 40, 20, 10, 0,
-    0, 10, 20, 20, 30, 30, 20, 20, 10, 0,
-    0, 0, 10, 10, 10, 10, 10, 10, 0, 0,
-    0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-];
-
-const WHITE_KNIGHT_POSITIONAL_VALUE: [u32; 100] = [
+const KNIGHT_MIDGAME_POSITIONAL_VALUE: [u32; 100] = [
     0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
     0, 0, 10, 10, 10, 10, 10, 10, 0, 0,
     0, 10, 20, 20, 30, 30, 20, 20, 10, 0,
@@ -62,20 +64,7 @@ const WHITE_KNIGHT_POSITIONAL_VALUE: [u32; 100] = [
     0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
 ];
 
-const BLACK_ROOK_POSITIONAL_VALUE: [u32; 100] = [
-    0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-    0, 50, 20, 20, 50, 50, 50, 0, 50, 0,
-    0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-    0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-    0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-    0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-    0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-    0, 30, 30, 30, 30, 30, 30, 30, 30, 0,
-    0, 30, 30, 30, 30, 30, 30, 30, 30, 0,
-    0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-];
-
-const WHITE_ROOK_POSITIONAL_VALUE: [u32; 100] = [
+const ROOK_MIDGAME_POSITIONAL_VALUE: [u32; 100] = [
     0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
     0, 30, 30, 30, 30, 30, 30, 30, 30, 0,
     0, 30, 30, 30, 30, 30, 30, 30, 30, 0,
@@ -88,20 +77,7 @@ const WHITE_ROOK_POSITIONAL_VALUE: [u32; 100] = [
     0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
 ];
 
-const BLACK_BISHOP_POSITIONAL_VALUE: [u32; 100] = [
-    0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-    0, 0, 0, 30, 0, 0, 30, 0, 0, 0,
-    0, 0, 40, 0, 30, 30, 0, 40, 0, 0,
-    0, 0, 0, 20, 20, 20, 20, 0, 0, 0,
-    0, 0, 20, 40, 30, 30, 40, 20, 0, 0,
-    0, 0, 40, 30, 20, 20, 30, 40, 0, 0,
-    0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-    0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-    0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-    0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-];
-
-const WHITE_BISHOP_POSITIONAL_VALUE: [u32; 100] = [
+const BISHOP_MIDGAME_POSITIONAL_VALUE: [u32; 100] = [
     0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
     0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
     0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
@@ -114,7 +90,7 @@ const WHITE_BISHOP_POSITIONAL_VALUE: [u32; 100] = [
     0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
 ];
 
-const BLACK_QUEEN_POSITIONAL_VALUE: [u32; 100] = [
+const QUEEN_MIDGAME_POSITIONAL_VALUE: [u32; 100] = [
     0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
     0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
     0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
@@ -127,8 +103,8 @@ const BLACK_QUEEN_POSITIONAL_VALUE: [u32; 100] = [
     0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
 ];
 
-const WHITE_QUEEN_POSITIONAL_VALUE: [u32; 100] = [
-    0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+//100's encourage castling if possible. Otherwise stay in place.
+const KING_MIDGAME_POSITIONAL_VALUE: [u32; 100] = [
     0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
     0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
     0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
@@ -137,33 +113,86 @@ const WHITE_QUEEN_POSITIONAL_VALUE: [u32; 100] = [
     0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
     0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
     0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+    0, 0, 100, 100, 0, 50, 0, 100, 0, 0,
     0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
 ];
 
-//100's encourage castling if possible. Otherwise stay in place.
-const BLACK_KING_POSITIONAL_VALUE: [u32; 100] = [
-    0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-    0, 0, 100, 100, 0, 50, 0, 100, 0, 0,
-    0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-    0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-    0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-    0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+const PAWN_ENDGAME_POSITIONAL_VALUE: [u32; 100] = [
     0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+    0, 100, 100, 100, 100, 100, 100, 100, 100, 0,
+    0, 80, 80, 80, 80, 80, 80, 80, 80, 0,
+    0, 50, 50, 50, 50, 50, 50, 50, 50, 0,
+    0, 30, 30, 30, 30, 30, 30, 30, 30, 0,
+    0, 15, 15, 15, 15, 15, 15, 15, 15, 0,
+    0, 5, 5, 5, 5, 5, 5, 5, 5, 0,
     0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
     0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
     0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
 ];
 
-const WHITE_KING_POSITIONAL_VALUE: [u32; 100] = [
+const KNIGHT_ENDGAME_POSITIONAL_VALUE: [u32; 100] = [
     0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+    0, 0, 5, 10, 15, 15, 10, 5, 0, 0,
+    0, 5, 10, 15, 20, 20, 15, 10, 5, 0,
+    0, 10, 15, 20, 25, 25, 20, 15, 10, 0,
+    0, 15, 20, 25, 30, 30, 25, 20, 15, 0,
+    0, 15, 20, 25, 30, 30, 25, 20, 15, 0,
+    0, 10, 15, 20, 25, 25, 20, 15, 10, 0,
+    0, 5, 10, 15, 20, 20, 15, 10, 5, 0,
+    0, 0, 5, 10, 15, 15, 10, 5, 0, 0,
     0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+];
+
+const BISHOP_ENDGAME_POSITIONAL_VALUE: [u32; 100] = [
     0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+    0, 0, 4, 8, 12, 12, 8, 4, 0, 0,
+    0, 4, 8, 12, 16, 16, 12, 8, 4, 0,
+    0, 8, 12, 16, 20, 20, 16, 12, 8, 0,
+    0, 12, 16, 20, 24, 24, 20, 16, 12, 0,
+    0, 12, 16, 20, 24, 24, 20, 16, 12, 0,
+    0, 8, 12, 16, 20, 20, 16, 12, 8, 0,
+    0, 4, 8, 12, 16, 16, 12, 8, 4, 0,
+    0, 0, 4, 8, 12, 12, 8, 4, 0, 0,
     0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+];
+
+const ROOK_ENDGAME_POSITIONAL_VALUE: [u32; 100] = [
     0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+    0, 10, 10, 10, 10, 10, 10, 10, 10, 0,
+    0, 20, 20, 20, 20, 20, 20, 20, 20, 0,
+    0, 10, 10, 10, 10, 10, 10, 10, 10, 0,
+    0, 10, 10, 10, 10, 10, 10, 10, 10, 0,
+    0, 10, 10, 10, 10, 10, 10, 10, 10, 0,
+    0, 10, 10, 10, 10, 10, 10, 10, 10, 0,
+    0, 10, 10, 10, 10, 10, 10, 10, 10, 0,
+    0, 10, 10, 10, 10, 10, 10, 10, 10, 0,
     0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+];
+
+const QUEEN_ENDGAME_POSITIONAL_VALUE: [u32; 100] = [
     0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+    0, 0, 3, 6, 9, 9, 6, 3, 0, 0,
+    0, 3, 6, 9, 12, 12, 9, 6, 3, 0,
+    0, 6, 9, 12, 15, 15, 12, 9, 6, 0,
+    0, 9, 12, 15, 18, 18, 15, 12, 9, 0,
+    0, 9, 12, 15, 18, 18, 15, 12, 9, 0,
+    0, 6, 9, 12, 15, 15, 12, 9, 6, 0,
+    0, 3, 6, 9, 12, 12, 9, 6, 3, 0,
+    0, 0, 3, 6, 9, 9, 6, 3, 0, 0,
     0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-    0, 0, 100, 100, 0, 50, 0, 100, 0, 0,
+];
+
+//Without enough material left to mate it, the king is safest and most useful in the centre
+const KING_ENDGAME_POSITIONAL_VALUE: [u32; 100] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+    0, 0, 8, 16, 24, 24, 16, 8, 0, 0,
+    0, 8, 16, 24, 32, 32, 24, 16, 8, 0,
+    0, 16, 24, 32, 40, 40, 32, 24, 16, 0,
+    0, 24, 32, 40, 48, 48, 40, 32, 24, 0,
+    0, 24, 32, 40, 48, 48, 40, 32, 24, 0,
+    0, 16, 24, 32, 40, 40, 32, 24, 16, 0,
+    0, 8, 16, 24, 32, 32, 24, 16, 8, 0,
+    0, 0, 8, 16, 24, 24, 16, 8, 0, 0,
     0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
 ];
 
@@ -174,184 +203,820 @@ const ROOK_VALUE: u32 = 500;
 const QUEEN_VALUE: u32 = 900;
 const KING_VALUE: u32 = 10000;
 
-pub struct Eval {
-    cache: HashMap<String, (u32, i32)>
+//Game phase from the pieces left on the board: 24 with every piece still there, 0 with only kings and pawns
+const KNIGHT_PHASE: u32 = 1;
+const BISHOP_PHASE: u32 = 1;
+const ROOK_PHASE: u32 = 2;
+const QUEEN_PHASE: u32 = 4;
+const MAX_GAME_PHASE: u32 = 24;
+
+const MATE_VALUE: i32 = 1_000_000;
+const MAX_MATE_PLY: i32 = 1000;
+//Above any evaluation and below any mate the search can find
+const TABLEBASE_WIN_VALUE: i32 = 20_000;
+const MAX_CACHE_ENTRIES: usize = 1 << 20;
+const PAWN_CACHE_ENTRIES: usize = 1 << 14;
+//Bounds of a full window; beyond any mate score and safe to negate
+const INFINITY: i32 = MATE_VALUE + 1;
+const ASPIRATION_MIN_DEPTH: u32 = 4;
+const ASPIRATION_WINDOW: i32 = 50;
+const NULL_MOVE_MIN_DEPTH: u32 = 3;
+const NULL_MOVE_REDUCTION: u32 = 2;
+const NULL_MOVE_DEEP_DEPTH: u32 = 7;
+const LATE_MOVE_REDUCTION_MIN_DEPTH: u32 = 3;
+const LATE_MOVE_REDUCTION_MIN_MOVE_COUNT: u32 = 3;
+const FUTILITY_MAX_DEPTH: u32 = 2;
+const FUTILITY_MARGIN: i32 = 200;
+const REVERSE_FUTILITY_MAX_DEPTH: u32 = 3;
+const REVERSE_FUTILITY_MARGIN: i32 = 120;
+const MAX_EXTENDED_DEPTH_FACTOR: u32 = 2;
+const NODES_BETWEEN_STOP_CHECKS: u64 = 1024;
+
+#[derive(Copy, Clone)]
+#[derive(Debug)]
+#[derive(PartialEq)]
+pub enum Score {
+    Centipawns(i32),
+    //Moves until mate; negative when the side to move is the one being mated
+    Mate(i32),
+    //Played from the opening book without a search, so there is nothing to score it by
+    Book,
 }
 
-impl Eval {
-    pub fn new(board: &Board, depth: u32) -> Option<Move> {
-        let mut evaluation = Eval { cache: HashMap::new() };
-        evaluation.get_best_move(board, depth)
+impl Score {
+    fn from_value(value: i32) -> Score {
+        if value.abs() < MATE_VALUE - MAX_MATE_PLY {
+            return Score::Centipawns(value);
+        }
+        let plies_to_mate = MATE_VALUE - value.abs();
+        let moves_to_mate = (plies_to_mate + 1) / 2;
+        match value > 0 {
+            true => Score::Mate(moves_to_mate),
+            false => Score::Mate(-moves_to_mate),
+        }
     }
+}
 
-    fn get_best_move(&mut self, board: &Board, depth: u32) -> Option<Move> {
-        let mut evaluation_cache: HashMap<String, (u32, i32)> = HashMap::new();
-        let min_max_evaluation = self.min_max_evaluation(board, depth, true, i32::min_value(), i32::max_value(), true);
-        min_max_evaluation.1
+impl fmt::Display for Score {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Score::Centipawns(value) => write!(f, "cp {}", value),
+            Score::Mate(moves) => write!(f, "mate {}", moves),
+            Score::Book => write!(f, "book"),
+        }
     }
+}
+
+pub struct SearchResult {
+    pub best_move: Option<Move>,
+    pub score: Score,
+    pub pv: Vec<Move>,
+    pub depth: u32,
+    pub seldepth: u32,
+    pub nodes: u64,
+    pub time: Duration,
+    //Permille of the evaluation cache in use, as reported by UCI engines
+    pub hashfull: u32,
+}
+
+#[derive(Copy, Clone)]
+#[derive(Debug)]
+pub struct EngineOptions {
+    pub cache_size: usize,
+    //Lazy SMP: helper threads share the cache with the main thread; 1 keeps the search deterministic
+    pub threads: usize,
+    //Without it the horizon is evaluated as it stands, even in the middle of an exchange
+    pub quiescence: bool,
+    //Each selective-search technique can be switched off to measure what it is worth
+    pub null_move_pruning: bool,
+    pub late_move_reductions: bool,
+    pub futility_pruning: bool,
+    pub reverse_futility_pruning: bool,
+    pub check_extensions: bool,
+    //Book moves are only played this many plies into the game, and only when a book has been set
+    pub book_depth: u32,
+    pub book_selection: BookSelection,
+    //Positions with as many pieces as the largest tablebase tables are only probed this many plies from the horizon
+    pub tablebase_probe_depth: u32,
+}
 
-    fn min_max_evaluation(&mut self, board: &Board, depth: u32, is_maximizer: bool, alpha: i32, beta: i32, root: bool) -> (i32, Option<Move>) {
-        if let Some(cached_result) = self.cache.get(&board.board_string_with_turn_bit) {
-            let (cached_depth, best_move_value): (u32, i32) = *cached_result;
-            return (best_move_value.clone(), None);
+impl Default for EngineOptions {
+    fn default() -> EngineOptions {
+        EngineOptions {
+            cache_size: MAX_CACHE_ENTRIES,
+            threads: 1,
+            quiescence: true,
+            null_move_pruning: true,
+            late_move_reductions: true,
+            futility_pruning: true,
+            reverse_futility_pruning: true,
+            check_extensions: true,
+            book_depth: 20,
+            book_selection: BookSelection::WeightedRandom,
+            tablebase_probe_depth: 1,
         }
-        
-        if depth == 0 {
-            let evaluations = get_snapshot_evaluation(&board);
-            let value = match board.current_turn.color {
-                Color::White => evaluations.0 as i32 - evaluations.1 as i32,
-                Color::Black => evaluations.1 as i32 - evaluations.0 as i32,
+    }
+}
+
+//Values are stored from the perspective of the side to move, with mate scores relative to the node,
+//so that entries stay valid between searches from different roots
+fn value_to_cache(value: i32, ply: usize) -> i32 {
+    if value >= MATE_VALUE - MAX_MATE_PLY {
+        value + ply as i32
+    } else if value <= -(MATE_VALUE - MAX_MATE_PLY) {
+        value - ply as i32
+    } else {
+        value
+    }
+}
+
+fn value_from_cache(value: i32, ply: usize) -> i32 {
+    if value >= MATE_VALUE - MAX_MATE_PLY {
+        value - ply as i32
+    } else if value <= -(MATE_VALUE - MAX_MATE_PLY) {
+        value + ply as i32
+    } else {
+        value
+    }
+}
+
+fn get_cached_value(entry: &CacheEntry, depth: u32, alpha: i32, beta: i32, ply: usize) -> Option<i32> {
+    if entry.depth < depth {
+        return None;
+    }
+    let value = value_from_cache(entry.value, ply);
+    match entry.bound {
+        Bound::Exact => Some(value),
+        Bound::Lower if value >= beta => Some(value),
+        Bound::Upper if value <= alpha => Some(value),
+        _ => None,
+    }
+}
+
+//Cloneable handle that lets another thread interrupt a running search
+#[derive(Clone)]
+pub struct StopHandle {
+    stop_flag: Arc<AtomicBool>,
+}
+
+impl StopHandle {
+    pub fn stop(&self) {
+        self.stop_flag.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_stopped(&self) -> bool {
+        self.stop_flag.load(Ordering::Relaxed)
+    }
+}
+
+pub struct Engine<E: Evaluator = ClassicalEvaluator> {
+    options: EngineOptions,
+    cache: TranspositionTable,
+    move_history: MoveHistory,
+    stop_flag: Arc<AtomicBool>,
+    evaluator: E,
+    opening_book: Option<OpeningBook>,
+    book_random: RandomGenerator,
+    tablebase: Option<Arc<dyn Tablebase>>,
+}
+
+impl Default for Engine {
+    fn default() -> Engine {
+        Engine::new()
+    }
+}
+
+impl Engine {
+    pub fn new() -> Engine {
+        Engine::with_options(EngineOptions::default())
+    }
+
+    pub fn with_options(options: EngineOptions) -> Engine {
+        Engine::with_evaluator(options, ClassicalEvaluator::new())
+    }
+}
+
+impl<E: Evaluator> Engine<E> {
+    pub fn with_evaluator(options: EngineOptions, evaluator: E) -> Engine<E> {
+        Engine {
+            options,
+            cache: TranspositionTable::new(options.cache_size),
+            move_history: MoveHistory::new(),
+            stop_flag: Arc::new(AtomicBool::new(false)),
+            evaluator,
+            opening_book: None,
+            book_random: RandomGenerator::new(get_clock_seed()),
+            tablebase: None,
+        }
+    }
+
+    pub fn set_opening_book(&mut self, opening_book: Option<OpeningBook>) {
+        self.opening_book = opening_book;
+    }
+
+    //Book choices are seeded from the clock unless a seed is set to replay them
+    pub fn set_book_seed(&mut self, seed: u64) {
+        self.book_random = RandomGenerator::new(seed);
+    }
+
+    pub fn set_tablebase(&mut self, tablebase: Option<Arc<dyn Tablebase>>) {
+        self.tablebase = tablebase;
+    }
+
+    pub fn options(&self) -> &EngineOptions {
+        &self.options
+    }
+
+    pub fn evaluator(&self) -> &E {
+        &self.evaluator
+    }
+
+    pub fn new_game(&mut self) {
+        self.cache.clear();
+        self.move_history.clear();
+    }
+
+    pub fn stop_handle(&self) -> StopHandle {
+        StopHandle { stop_flag: self.stop_flag.clone() }
+    }
+
+    //The stop flag is only cleared once the search is over, so that a stop asked for before it started still counts
+    pub fn search(&mut self, board: &Board, depth: u32) -> SearchResult {
+        let search_result = self.search_position(board, depth);
+        self.stop_flag.store(false, Ordering::Relaxed);
+        search_result
+    }
+
+    fn search_position(&mut self, board: &Board, depth: u32) -> SearchResult {
+        let start_time = Instant::now();
+        if let Some(book_move) = self.get_book_move(board) {
+            //Book moves come without a search, so there is no score or depth to report
+            return SearchResult {
+                best_move: Some(book_move),
+                score: Score::Book,
+                pv: vec![book_move],
+                depth: 0,
+                seldepth: 0,
+                nodes: 0,
+                time: start_time.elapsed(),
+                hashfull: self.cache.hashfull(),
+            };
+        }
+        //The tablebase knows the result, so the move keeping it needs no search either
+        if let Some((tablebase_move, wdl)) = self.tablebase.as_deref().and_then(|tablebase| tablebase::probe_root(tablebase, board)) {
+            return SearchResult {
+                best_move: Some(tablebase_move),
+                score: Score::from_value(get_tablebase_value(wdl)),
+                pv: vec![tablebase_move],
+                depth: 0,
+                seldepth: 0,
+                nodes: 0,
+                time: start_time.elapsed(),
+                hashfull: self.cache.hashfull(),
             };
+        }
+        self.move_history.age();
+        let cache = &self.cache;
+        let stop_flag: &AtomicBool = &self.stop_flag;
+        //Helpers only hear of a stop through the main thread, which stops them once it is done itself,
+        //so that ending a search normally does not show as stopped on the engine's stop flag
+        let helper_stop_flag = AtomicBool::new(false);
+        let helper_stop_flag: &AtomicBool = &helper_stop_flag;
+        let move_history = &mut self.move_history;
+        let evaluator = &mut self.evaluator;
+        let tablebase = self.tablebase.as_deref();
+        let options = self.options;
+        let helper_count = options.threads.saturating_sub(1);
 
-            if is_maximizer == true {
-                return (value, None);
-            } else {
-                return (-value, None);
+        let (mut search_result, helper_nodes) = thread::scope(|scope| {
+            let helpers: Vec<_> = (0..helper_count)
+                .map(|helper_index| {
+                    let helper_board = board.clone();
+                    let helper_move_history = move_history.clone();
+                    let helper_evaluator = evaluator.clone();
+                    scope.spawn(move || {
+                        //Half of the helpers look one ply deeper so that the threads diverge
+                        let helper_depth = depth + (helper_index % 2) as u32;
+                        let mut searcher = Searcher::new(cache, helper_stop_flag, options, helper_move_history, helper_evaluator, tablebase);
+                        searcher.iterative_deepening(&helper_board, helper_depth, start_time);
+                        searcher.nodes
+                    })
+                })
+                .collect();
+
+            let mut searcher = Searcher::new(cache, stop_flag, options, mem::take(move_history), evaluator.clone(), tablebase);
+            let search_result = searcher.iterative_deepening(board, depth, start_time);
+            *move_history = searcher.move_history;
+            *evaluator = searcher.evaluator;
+            helper_stop_flag.store(true, Ordering::Relaxed);
+            let helper_nodes: u64 = helpers.into_iter()
+                .map(|helper| helper.join().expect("A search helper thread panicked"))
+                .sum();
+            (search_result, helper_nodes)
+        });
+
+        search_result.nodes += helper_nodes;
+        search_result.time = start_time.elapsed();
+        search_result.hashfull = self.cache.hashfull();
+        search_result
+    }
+
+    fn get_book_move(&mut self, board: &Board) -> Option<Move> {
+        let opening_book = self.opening_book.as_ref()?;
+        if board.ply >= self.options.book_depth {
+            return None;
+        }
+        opening_book.pick_move(board, self.options.book_selection, self.book_random.next_u64())
+    }
+}
+
+//Per-thread search state; the cache and stop flag are shared by every thread searching the same position
+struct Searcher<'a, E: Evaluator> {
+    cache: &'a TranspositionTable,
+    stop_flag: &'a AtomicBool,
+    options: EngineOptions,
+    move_history: MoveHistory,
+    //Moves leading from the root to the node being searched, with None standing for a null move
+    current_line: Vec<Option<Move>>,
+    //Depth of the iteration in progress, which bounds how far extensions may stretch a line
+    root_depth: u32,
+    can_stop: bool,
+    is_stopped: bool,
+    nodes: u64,
+    seldepth: u32,
+    //Triangular PV table: pv_table[ply] holds the best line found from that ply onwards
+    pv_table: Vec<Vec<Move>>,
+    evaluator: E,
+    tablebase: Option<&'a dyn Tablebase>,
+}
+
+impl<'a, E: Evaluator> Searcher<'a, E> {
+    fn new(cache: &'a TranspositionTable, stop_flag: &'a AtomicBool, options: EngineOptions, move_history: MoveHistory, evaluator: E, tablebase: Option<&'a dyn Tablebase>) -> Searcher<'a, E> {
+        Searcher {
+            cache,
+            stop_flag,
+            options,
+            move_history,
+            current_line: Vec::new(),
+            root_depth: 0,
+            can_stop: false,
+            is_stopped: false,
+            nodes: 0,
+            seldepth: 0,
+            pv_table: Vec::new(),
+            evaluator,
+            tablebase,
+        }
+    }
+
+    //Deepens one ply at a time so that a stopped search can fall back on the last completed iteration
+    fn iterative_deepening(&mut self, board: &Board, depth: u32, start_time: Instant) -> SearchResult {
+        //The first iteration always runs to completion so that there is a move to fall back on
+        self.can_stop = false;
+        let (mut value, mut search_result) = self.search_to_depth(board, 1, None, start_time)
+            .expect("The first iteration cannot be stopped");
+        self.can_stop = true;
+
+        for current_depth in 2..=depth {
+            //Checked between iterations as well, as shallow ones can finish before the first node count check
+            if self.stop_flag.load(Ordering::Relaxed) {
+                break;
+            }
+            let previous_value = Some(value).filter(|_| current_depth >= ASPIRATION_MIN_DEPTH);
+            match self.search_to_depth(board, current_depth, previous_value, start_time) {
+                Some((iteration_value, iteration_result)) => {
+                    value = iteration_value;
+                    search_result = iteration_result;
+                },
+                None => break,
             }
         }
 
-        let legal_moves = get_legal_moves_sorted_by_strength(&board);
+        search_result
+    }
 
-        let (best_move_value, best_move) = self.get_best_move_with_value(&board, depth, legal_moves, is_maximizer, alpha, beta);
-        self.cache.insert(board.board_string_with_turn_bit.clone(), (depth, best_move_value));
-        (best_move_value, best_move)
+    fn search_to_depth(&mut self, board: &Board, depth: u32, previous_value: Option<i32>, start_time: Instant) -> Option<(i32, SearchResult)> {
+        let (value, best_move) = self.aspiration_search(board, depth, previous_value);
+        if self.is_stopped {
+            return None;
+        }
+        Some((value, SearchResult {
+            best_move,
+            score: Score::from_value(value),
+            pv: self.pv_table[0].clone(),
+            depth,
+            seldepth: self.seldepth,
+            nodes: self.nodes,
+            time: start_time.elapsed(),
+            hashfull: 0,
+        }))
     }
 
-    fn get_best_move_with_value(&mut self, board: &Board, depth: u32, legal_moves: Vec<Move>, is_maximizer:bool, mut alpha: i32, mut beta: i32) -> (i32, Option<Move>) {
-        let mut best_move: Option<Move> = None;
-        let mut best_move_value = match is_maximizer {
-            true => i32::min_value(),
-            false => i32::max_value()
+    //Searches a narrow window around the previous iteration's value first, and widens it on whichever
+    //side the value falls outside of until the value lands inside
+    fn aspiration_search(&mut self, board: &Board, depth: u32, previous_value: Option<i32>) -> (i32, Option<Move>) {
+        self.root_depth = depth;
+        let (mut alpha, mut beta) = match previous_value {
+            Some(value) if !is_mate_value(value) => (value - ASPIRATION_WINDOW, value + ASPIRATION_WINDOW),
+            _ => (-INFINITY, INFINITY),
         };
+        let mut delta = ASPIRATION_WINDOW;
 
-        for legal_move in legal_moves.into_iter() {
-            let next_board = board.test_move(Move { from: legal_move.from, to: legal_move.to });
-            let value: i32 = self.min_max_evaluation(&next_board, depth - 1, !is_maximizer, alpha, beta, false).0;
+        loop {
+            let (value, best_move) = self.negamax(board, depth, alpha, beta, 0);
+            if self.is_stopped {
+                return (value, best_move);
+            }
+            if value <= alpha {
+                alpha = match is_mate_value(value) {
+                    true => -INFINITY,
+                    false => cmp::max(value - delta, -INFINITY),
+                };
+            } else if value >= beta {
+                beta = match is_mate_value(value) {
+                    true => INFINITY,
+                    false => cmp::min(value + delta, INFINITY),
+                };
+            } else {
+                return (value, best_move);
+            }
+            delta *= 2;
+        }
+    }
 
-            match is_maximizer {
-                true => {
-                    if value > best_move_value {
-                        best_move_value = value;
-                        best_move = Some(legal_move);
-                    }
-                    alpha = cmp::max(alpha, value);
-                },
+    fn should_stop(&mut self) -> bool {
+        if self.can_stop && !self.is_stopped && self.nodes.is_multiple_of(NODES_BETWEEN_STOP_CHECKS) {
+            self.is_stopped = self.stop_flag.load(Ordering::Relaxed);
+        }
+        self.is_stopped
+    }
+
+    //Principal variation search: values are from the side to move's perspective, and every move after
+    //the first is only searched with a null window unless it proves to be better
+    fn negamax(&mut self, board: &Board, depth: u32, mut alpha: i32, beta: i32, ply: usize) -> (i32, Option<Move>) {
+        self.nodes += 1;
+        if self.should_stop() {
+            return (0, None);
+        }
+        self.seldepth = cmp::max(self.seldepth, ply as u32);
+        if self.pv_table.len() <= ply {
+            self.pv_table.resize(ply + 1, Vec::new());
+        }
+        self.pv_table[ply].clear();
+        let is_pv_node = beta - alpha > 1;
+
+        let cache_entry = self.cache.probe(board.hash);
+        //PV nodes, the root among them, are always searched so that the principal variation stays complete
+        if !is_pv_node {
+            if let Some(cached_value) = cache_entry.and_then(|entry| get_cached_value(&entry, depth, alpha, beta, ply)) {
+                return (cached_value, None);
+            }
+        }
+
+        //The root is left to the search, which then only needs to tell the moves apart
+        if ply > 0 {
+            if let Some(wdl) = self.probe_wdl(board, depth) {
+                let value = get_tablebase_value(wdl);
+                self.cache.store(board.hash, CacheEntry { depth, value, bound: Bound::Exact, best_move: None });
+                return (value, None);
+            }
+        }
+
+        if depth == 0 {
+            return (self.quiescence(board, alpha, beta, ply), None);
+        }
+
+        let is_check = is_in_check(board, board.current_turn.color);
+        let static_evaluation = self.evaluator.evaluate(board);
+        let mut futility_value: Option<i32> = None;
+
+        //A side in check cannot afford to skip moves
+        if !is_pv_node && !is_check {
+            if self.options.reverse_futility_pruning && depth <= REVERSE_FUTILITY_MAX_DEPTH && !is_mate_value(beta)
+                && static_evaluation - REVERSE_FUTILITY_MARGIN * depth as i32 >= beta {
+                return (static_evaluation, None);
+            }
+
+            if self.options.null_move_pruning && depth >= NULL_MOVE_MIN_DEPTH && self.current_line.last() != Some(&None)
+                && !is_mate_value(beta) && static_evaluation >= beta && has_non_pawn_material(board, board.current_turn.color) {
+                if let Some(value) = self.null_move_search(board, depth, beta, ply) {
+                    return (value, None);
+                }
+                if self.is_stopped {
+                    return (0, None);
+                }
+            }
+
+            //Near the horizon a quiet move is not expected to lift a hopeless position above alpha
+            if self.options.futility_pruning && depth <= FUTILITY_MAX_DEPTH && !is_mate_value(alpha) {
+                futility_value = Some(static_evaluation + FUTILITY_MARGIN * depth as i32);
+            }
+        }
+        let allow_reductions = self.options.late_move_reductions && ply > 0 && !is_check;
+        //Extensions stop once a line is twice as long as the iteration's depth, so checks cannot
+        //keep a line growing forever
+        let allow_extensions = self.options.check_extensions && (ply as u32) < MAX_EXTENDED_DEPTH_FACTOR * self.root_depth;
+        let has_single_reply = allow_extensions && is_check && get_all_legal_moves(board).len() == 1;
+
+        let previous_move = self.current_line.last().copied().flatten();
+        let mut move_picker = MovePicker::new(board, cache_entry.and_then(|entry| entry.best_move), &self.move_history, ply, previous_move);
+        let original_alpha = alpha;
+        let mut best_move: Option<Move> = None;
+        let mut best_move_value = -INFINITY;
+        let mut tried_quiet_moves: Vec<Move> = Vec::new();
+        let mut move_count: u32 = 0;
+
+        while let Some(legal_move) = move_picker.next_move(board, &self.move_history) {
+            let next_board = board.test_move(legal_move);
+            if is_in_check(&next_board, board.current_turn.color) {
+                continue;
+            }
+            move_count += 1;
+            let is_quiet = board.get_piece_at(legal_move.to).is_none();
+            let gives_check = is_in_check(&next_board, next_board.current_turn.color);
+            let is_quiet_and_calm = is_quiet && !gives_check;
+            //Checks and forced replies do not use up depth, so mating nets just past the horizon are still seen
+            let new_depth = match allow_extensions && (gives_check || has_single_reply) {
+                true => depth,
+                false => depth - 1,
+            };
+
+            if let Some(futility_value) = futility_value {
+                if move_count > 1 && is_quiet_and_calm && futility_value <= alpha {
+                    continue;
+                }
+            }
+
+            let reduction = match allow_reductions && is_quiet_and_calm && depth >= LATE_MOVE_REDUCTION_MIN_DEPTH && move_count > LATE_MOVE_REDUCTION_MIN_MOVE_COUNT {
+                true => get_late_move_reduction(depth, move_count),
+                false => 0,
+            };
+
+            self.push_move(board, Some(legal_move), &next_board);
+            let value = match move_count == 1 {
+                true => -self.negamax(&next_board, new_depth, -beta, -alpha, ply + 1).0,
                 false => {
-                    if value < best_move_value {
-                        best_move_value = value;
-                        best_move = Some(legal_move);
+                    let mut value = -self.negamax(&next_board, new_depth - reduction, -alpha - 1, -alpha, ply + 1).0;
+                    //A reduced move that beats alpha is searched again at full depth, and one that still
+                    //beats alpha in a PV node gets the full window to find its exact value
+                    if value > alpha && reduction > 0 && !self.is_stopped {
+                        value = -self.negamax(&next_board, new_depth, -alpha - 1, -alpha, ply + 1).0;
                     }
-                    beta = cmp::min(beta, value);
+                    if value > alpha && value < beta && !self.is_stopped {
+                        value = -self.negamax(&next_board, new_depth, -beta, -alpha, ply + 1).0;
+                    }
+                    value
                 },
             };
+            self.pop_move();
+            if self.is_stopped {
+                return (best_move_value, best_move);
+            }
+            if is_quiet {
+                tried_quiet_moves.push(legal_move);
+            }
 
-            if beta <= alpha {
+            if value > best_move_value {
+                best_move_value = value;
+                best_move = Some(legal_move);
+                self.update_pv(ply, legal_move);
+            }
+            alpha = cmp::max(alpha, value);
+
+            if alpha >= beta {
+                if is_quiet {
+                    self.move_history.update_on_cutoff(board, ply, depth, legal_move, previous_move, &tried_quiet_moves);
+                }
                 break;
             }
         }
+
+        if best_move.is_none() {
+            return match is_check {
+                true => (-(MATE_VALUE - ply as i32), None),
+                false => (0, None),
+            };
+        }
+
+        let bound = Bound::from_window(best_move_value, original_alpha, beta);
+        let entry = CacheEntry { depth, value: value_to_cache(best_move_value, ply), bound, best_move };
+        self.cache.store(board.hash, entry);
         (best_move_value, best_move)
     }
+
+    //Lets the opponent move twice in a row with a reduced search; if the side to move still holds beta
+    //the position is good enough to cut off without searching any real move
+    fn null_move_search(&mut self, board: &Board, depth: u32, beta: i32, ply: usize) -> Option<i32> {
+        let reduction = match depth >= NULL_MOVE_DEEP_DEPTH {
+            true => NULL_MOVE_REDUCTION + 1,
+            false => NULL_MOVE_REDUCTION,
+        };
+        let mut null_board = board.clone();
+        null_board.make_null_move();
+
+        self.push_move(board, None, &null_board);
+        let value = -self.negamax(&null_board, depth.saturating_sub(1 + reduction), -beta, -beta + 1, ply + 1).0;
+        self.pop_move();
+
+        match !self.is_stopped && value >= beta {
+            true => Some(beta),
+            false => None,
+        }
+    }
+
+    //Only captures that do not lose material are searched, so that the static evaluation is never
+    //taken in the middle of an exchange. The side to move may always stand pat instead
+    fn quiescence(&mut self, board: &Board, mut alpha: i32, beta: i32, ply: usize) -> i32 {
+        if self.should_stop() {
+            return 0;
+        }
+        self.seldepth = cmp::max(self.seldepth, ply as u32);
+
+        let stand_pat = self.evaluator.evaluate(board);
+        if stand_pat >= beta || !self.options.quiescence {
+            return stand_pat;
+        }
+        alpha = cmp::max(alpha, stand_pat);
+
+        let mut best_value = stand_pat;
+        for capture in move_ordering::get_winning_captures(board) {
+            let next_board = board.test_move(capture);
+            if is_in_check(&next_board, board.current_turn.color) {
+                continue;
+            }
+            self.nodes += 1;
+            self.push_move(board, Some(capture), &next_board);
+            let value = -self.quiescence(&next_board, -beta, -alpha, ply + 1);
+            self.pop_move();
+            if self.is_stopped {
+                break;
+            }
+            best_value = cmp::max(best_value, value);
+            alpha = cmp::max(alpha, value);
+            if alpha >= beta {
+                break;
+            }
+        }
+        best_value
+    }
+
+    fn probe_wdl(&self, board: &Board, depth: u32) -> Option<Wdl> {
+        let tablebase = self.tablebase?;
+        match tablebase::should_probe(tablebase, board, depth, self.options.tablebase_probe_depth) {
+            true => tablebase.probe_wdl(board),
+            false => None,
+        }
+    }
+
+    fn push_move(&mut self, board: &Board, chess_move: Option<Move>, next_board: &Board) {
+        self.current_line.push(chess_move);
+        self.evaluator.push_move(board, chess_move, next_board);
+    }
+
+    fn pop_move(&mut self) {
+        self.current_line.pop();
+        self.evaluator.pop_move();
+    }
+
+    fn update_pv(&mut self, ply: usize, best_move: Move) {
+        let mut line = vec![best_move];
+        line.extend_from_slice(&self.pv_table[ply + 1]);
+        self.pv_table[ply] = line;
+    }
+}
+
+//Cursed wins and blessed losses are lost to the fifty-move rule, but still better than a real draw or loss
+fn get_tablebase_value(wdl: Wdl) -> i32 {
+    match wdl {
+        Wdl::Win => TABLEBASE_WIN_VALUE,
+        Wdl::CursedWin => 1,
+        Wdl::Draw => 0,
+        Wdl::BlessedLoss => -1,
+        Wdl::Loss => -TABLEBASE_WIN_VALUE,
+    }
+}
+
+fn get_clock_seed() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |time| time.as_nanos() as u64)
+}
+
+//Also true for the infinite bounds of a full window
+fn is_mate_value(value: i32) -> bool {
+    value >= MATE_VALUE - MAX_MATE_PLY || value <= -(MATE_VALUE - MAX_MATE_PLY)
+}
+
+//Without pieces the side to move is often in zugzwang, where passing would be the best move
+fn has_non_pawn_material(board: &Board, color: Color) -> bool {
+    board.squares.iter()
+        .filter_map(|square| square.piece)
+        .any(|piece| piece.color == color && piece.piece_type != PieceType::Pawn && piece.piece_type != PieceType::King)
+}
+
+//Grows with both the depth left and how late the move comes, and always leaves at least one ply to search
+fn get_late_move_reduction(depth: u32, move_count: u32) -> u32 {
+    let reduction = 0.75 + (depth as f64).ln() * (move_count as f64).ln() / 2.25;
+    cmp::min(reduction as u32, depth - 2)
+}
+
+pub struct Eval;
+
+impl Eval {
+    #[allow(clippy::new_ret_no_self)]
+    pub fn new(board: &Board, depth: u32) -> SearchResult {
+        Engine::new().search(board, depth)
+    }
 }
 
 pub fn get_all_legal_moves(board: &Board) -> Vec<Move> {
-    let test_board: Board = board.clone();
-    let legal_moves: Vec<Move> = test_board.squares.into_iter()
+    let legal_moves: Vec<Move> = board.squares.iter()
         .enumerate()
-        .filter(|&(_i, square)| match square.piece {
-            Some(p) => {
-                if p.color == board.current_turn.color {
-                    return true;
-                }
-                false
-            },
-            None => false,
-        })
-        .flat_map(|(i, square)| {
-            let test_board = board.clone();
-            square.piece.unwrap().get_moves(i, Rc::new(test_board))
+        .filter_map(|(i, square)| match square.piece {
+            Some(p) if p.color == board.current_turn.color => Some((i, p)),
+            _ => None,
         })
+        .flat_map(|(i, piece)| piece.get_moves(i, board))
         .collect();
 
     legal_moves
 }
 
-fn get_legal_moves_sorted_by_strength(board: &Board) -> Vec<Move> {
-    let legal_moves = get_all_legal_moves(board);
-    let legal_moves_sorted: Vec<Move> = legal_moves.into_iter()
-        .map(|legal_move| {
-            let next_board = board.test_move(Move { from: legal_move.from, to: legal_move.to });
-            let evaluations = get_snapshot_evaluation(&next_board);
-            match next_board.current_turn.color {
-                Color::White => (legal_move, evaluations.0 as i32 - evaluations.1 as i32),
-                Color::Black => (legal_move, evaluations.1 as i32 - evaluations.0 as i32),
-            }
-        })
-        .sorted_by(|x, y| x.1.cmp(&y.1))
-        .map(|(legal_move, _)| legal_move)
-        .collect();
-    legal_moves_sorted
+//Static evaluation from the side to move's perspective
+pub fn evaluate(board: &Board) -> i32 {
+    evaluate_with_params(board, &DEFAULT_EVAL_PARAMS)
 }
 
-fn get_value_of_white_piece(piece: Piece, board_index: usize) -> u32 {
-    match piece.piece_type {
-        PieceType::Pawn =>  WHITE_PAWN_POSITIONAL_VALUE[board_index] + PAWN_VALUE,
-        PieceType::Knight =>  WHITE_KNIGHT_POSITIONAL_VALUE[board_index] + KNIGHT_VALUE,
-        PieceType::Bishop =>  WHITE_BISHOP_POSITIONAL_VALUE[board_index] + BISHOP_VALUE,
-        PieceType::Rook =>  WHITE_ROOK_POSITIONAL_VALUE[board_index] + ROOK_VALUE,
-        PieceType::Queen =>  WHITE_QUEEN_POSITIONAL_VALUE[board_index] + QUEEN_VALUE,
-        PieceType::King => WHITE_KING_POSITIONAL_VALUE[board_index] + KING_VALUE,
-    }
+pub fn evaluate_with_params(board: &Board, params: &EvalParams) -> i32 {
+    let phase = get_game_phase(board);
+    let pawn_structure_value = pawn_structure::evaluate_pawn_structure(board, phase);
+    get_side_to_move_evaluation(board, params, pawn_structure_value, phase)
 }
 
-fn get_value_of_black_piece(piece: Piece, board_index: usize) -> u32 {
-    match piece.piece_type {
-        PieceType::Pawn =>  BLACK_PAWN_POSITIONAL_VALUE[board_index] + PAWN_VALUE,
-        PieceType::Knight =>  BLACK_KNIGHT_POSITIONAL_VALUE[board_index] + KNIGHT_VALUE,
-        PieceType::Bishop =>  BLACK_BISHOP_POSITIONAL_VALUE[board_index] + BISHOP_VALUE,
-        PieceType::Rook =>  BLACK_ROOK_POSITIONAL_VALUE[board_index] + ROOK_VALUE,
-        PieceType::Queen =>  BLACK_QUEEN_POSITIONAL_VALUE[board_index] + QUEEN_VALUE,
-        PieceType::King => BLACK_KING_POSITIONAL_VALUE[board_index] + KING_VALUE,
+//The pawn structure value is from white's perspective, as it comes out of the pawn hash table
+fn get_side_to_move_evaluation(board: &Board, params: &EvalParams, pawn_structure_value: i32, phase: u32) -> i32 {
+    let white_advantage = get_terms_evaluation(board, params, pawn_structure_value, phase);
+    let white_advantage = endgame::evaluate_endgame(board, params, white_advantage);
+    match board.current_turn.color {
+        Color::White => white_advantage,
+        Color::Black => -white_advantage,
     }
 }
 
-fn get_white_evaluation(board: &Board) -> u32 {
-    let mut value: u32 = 0;
-    let board = board.clone();
-    board.squares.into_iter()
-        .enumerate()
-        .for_each(|(i, square)| {
-            value = value + match square.piece {
-                Some(p) => match p.color {
-                    Color::White => get_value_of_white_piece(p, i),
-                    _ => 0
-                },
-                None => 0,
-            }
-        });
+//From white's perspective, before the endgame knowledge adjusts it
+fn get_terms_evaluation(board: &Board, params: &EvalParams, pawn_structure_value: i32, phase: u32) -> i32 {
+    let evaluations = get_snapshot_evaluation(board, params);
+    evaluations.0 as i32 - evaluations.1 as i32 + pawn_structure_value
+        + piece_activity::evaluate_piece_activity(board, phase)
+        + king_safety::evaluate_king_safety(board, phase)
+}
 
-    value
+fn get_value_of_piece(params: &EvalParams, piece: Piece, board_index: usize, phase: u32) -> u32 {
+    let piece_params = params.get_piece_params(piece.piece_type);
+    let midgame_value = piece_params.get_square_value(piece.color, true, board_index);
+    let endgame_value = piece_params.get_square_value(piece.color, false, board_index);
+    taper(midgame_value, endgame_value, phase) + piece_params.value
 }
 
-fn get_black_evaluation(board: &Board) -> u32 {
-    let mut value: u32 = 0;
-    let board = board.clone();
-    board.squares.into_iter()
-        .enumerate()
-        .for_each(|(i, square)| {
-            value = value + match square.piece {
-                Some(p) => match p.color {
-                    Color::Black => get_value_of_black_piece(p, i),
-                    _ => 0
-                },
-                None => 0,
-            }
-        });
+//Blends a midgame and an endgame value according to how much material is left
+fn taper(midgame_value: u32, endgame_value: u32, phase: u32) -> u32 {
+    (midgame_value * phase + endgame_value * (MAX_GAME_PHASE - phase)) / MAX_GAME_PHASE
+}
+
+fn get_game_phase(board: &Board) -> u32 {
+    let phase = board.squares.iter()
+        .filter_map(|square| square.piece)
+        .map(|piece| match piece.piece_type {
+            PieceType::Knight => KNIGHT_PHASE,
+            PieceType::Bishop => BISHOP_PHASE,
+            PieceType::Rook => ROOK_PHASE,
+            PieceType::Queen => QUEEN_PHASE,
+            PieceType::Pawn | PieceType::King => 0,
+        })
+        .sum();
+    //Boards set up with extra pieces cannot push the phase beyond the starting position's
+    cmp::min(phase, MAX_GAME_PHASE)
+}
 
-    value
+fn get_white_evaluation(board: &Board, params: &EvalParams) -> u32 {
+    get_evaluation_of_color(board, params, Color::White)
 }
 
-fn get_snapshot_evaluation(board: &Board) -> (u32, u32) {
-    (get_white_evaluation(board), get_black_evaluation(board))
+fn get_black_evaluation(board: &Board, params: &EvalParams) -> u32 {
+    get_evaluation_of_color(board, params, Color::Black)
+}
+
+fn get_evaluation_of_color(board: &Board, params: &EvalParams, color: Color) -> u32 {
+    let phase = get_game_phase(board);
+    board.squares.iter()
+        .enumerate()
+        .map(|(i, square)| match square.piece {
+            Some(p) if p.color == color => get_value_of_piece(params, p, i, phase),
+            _ => 0,
+        })
+        .sum()
+}
+
+fn get_snapshot_evaluation(board: &Board, params: &EvalParams) -> (u32, u32) {
+    (get_white_evaluation(board, params), get_black_evaluation(board, params))
 }
 
 #[cfg(test)]
@@ -365,11 +1030,32 @@ mod tests {
         fn it_evaluates_a_board_position() {
             let board_string = String::from("00000000000rnbqkbnr00pppppppp00--------00--------00--------00--------00PPPPPPPP00RNBQKBNR00000000000");
             let board: Board = Board::new(board_string, Color::White);
-            let (white_value, black_value): (u32, u32) = get_snapshot_evaluation(&board);
+            let (white_value, black_value): (u32, u32) = get_snapshot_evaluation(&board, &DEFAULT_EVAL_PARAMS);
 
             assert_eq!(white_value, black_value);
         }
 
+        #[test]
+        fn it_evaluates_mirrored_positions_alike() {
+            //Openings, middlegames with uneven material and king safety, and pawn and piece endgames
+            let positions = [
+                "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b - - 0 1",
+                "r1bqkb1r/pppp1ppp/2n2n2/4p3/2B1P3/5N2/PPPP1PPP/RNBQK2R w - - 0 1",
+                "r1bq1rk1/pp3ppp/2n2n2/3p4/1b1P4/2N2N2/PP2BPPP/R2QK2R b - - 0 1",
+                "r4rk1/1pp2ppp/p1n5/3q4/3P2b1/2P2N2/P4PPP/R1BQR1K1 w - - 0 1",
+                "2kr3r/ppp2ppp/2n5/2b1p3/4P1q1/2NP1N2/PPP2PPP/R2Q1RK1 b - - 0 1",
+                "6k1/5ppp/8/3N4/8/1P6/5PPP/6K1 w - - 0 1",
+                "8/2p5/3k4/1p1p4/1P1P4/2PK4/8/8 w - - 0 1",
+                "4r1k1/5ppp/8/8/8/8/1R3PPP/6K1 b - - 0 1",
+                "8/8/4k3/8/2B5/8/3K4/1N6 w - - 0 1",
+                "r3k2r/1b3ppp/p3pn2/1pq5/4P3/P1N2Q2/1PB2PPP/R4RK1 w - - 0 1",
+            ];
+            for fen in positions.iter() {
+                let board = Board::from_fen(fen).unwrap();
+                assert_eq!(evaluate(&board), evaluate(&board.mirror()), "{}", fen);
+            }
+        }
+
         mod get_best_move {
             use super::*;
 
@@ -377,7 +1063,7 @@ mod tests {
             fn it_gives_best_move_with_one_depth() {
                 let board_string = String::from("00000000000rnbqkbnr00pppppppp00--------00--------00--------00--------00PPPPPPPP00RNBQKBNR00000000000");
                 let board: Board = Board::new(board_string, Color::White);
-                let best_move: Move = Eval::new(&board, 1).unwrap();
+                let best_move: Move = Eval::new(&board, 1).best_move.unwrap();
 
                 let expected_best_move = Move::from_chess_move((String::from("c2"), String::from("c4")));
                 assert_eq!((best_move.from, best_move.to), (expected_best_move.from, expected_best_move.to));
@@ -392,11 +1078,29 @@ mod tests {
                 board.make_move(Move::from_chess_move((String::from("h2"), String::from("h4"))));
                 board.make_move(Move::from_chess_move((String::from("h7"), String::from("h5"))));
                 board.make_move(Move::from_chess_move((String::from("c1"), String::from("g5"))));
-                let best_move: Move = Eval::new(&board, 1).unwrap();
+                let options = EngineOptions { quiescence: false, ..EngineOptions::default() };
+                let best_move: Move = Engine::with_options(options).search(&board, 1).best_move.unwrap();
                 let expected_best_move = Move::from_chess_move((String::from("d8"), String::from("g5")));
                 assert_eq!((best_move.from, best_move.to), (expected_best_move.from, expected_best_move.to));
             }
 
+            #[test]
+            fn it_sees_the_recapture_at_the_end_of_one_depth() {
+                let board_string = String::from("00000000000rnbqkbnr00pppppppp00--------00--------00--------00--------00PPPPPPPP00RNBQKBNR00000000000");
+                let mut board: Board = Board::new(board_string, Color::White);
+                board.make_move(Move::from_chess_move((String::from("d2"), String::from("d4"))));
+                board.make_move(Move::from_chess_move((String::from("e7"), String::from("e5"))));
+                board.make_move(Move::from_chess_move((String::from("h2"), String::from("h4"))));
+                board.make_move(Move::from_chess_move((String::from("h7"), String::from("h5"))));
+                board.make_move(Move::from_chess_move((String::from("c1"), String::from("g5"))));
+                let search_result = Eval::new(&board, 1);
+                let queen_blunder = Move::from_chess_move((String::from("d8"), String::from("g5")));
+
+                assert_ne!(search_result.best_move, Some(queen_blunder));
+                //The recapture on g5 is only found beyond the iteration depth
+                assert!(search_result.seldepth > 1);
+            }
+
             #[test]
             fn it_explores_move_tree_given_depth() {
                 let board_string = String::from("00000000000rnbqkbnr00pppppppp00--------00--------00--------00--------00PPPPPPPP00RNBQKBNR00000000000");
@@ -406,20 +1110,447 @@ mod tests {
                 board.make_move(Move::from_chess_move((String::from("h2"), String::from("h4"))));
                 board.make_move(Move::from_chess_move((String::from("h7"), String::from("h5"))));
                 board.make_move(Move::from_chess_move((String::from("c1"), String::from("g5"))));
-                let best_move: Move = Eval::new(&board, 4).unwrap();
+                let best_move: Move = Eval::new(&board, 4).best_move.unwrap();
                 let expected_best_move = Move::from_chess_move((String::from("f7"), String::from("f6")));
                 assert_eq!((best_move.from, best_move.to), (expected_best_move.from, expected_best_move.to));
             }
         }
 
+        mod search {
+            use super::*;
+
+            #[test]
+            fn it_returns_a_principal_variation_starting_with_the_best_move() {
+                let board_string = String::from("00000000000rnbqkbnr00pppppppp00--------00--------00--------00--------00PPPPPPPP00RNBQKBNR00000000000");
+                let mut board: Board = Board::new(board_string, Color::White);
+                board.make_move(Move::from_chess_move((String::from("d2"), String::from("d4"))));
+                board.make_move(Move::from_chess_move((String::from("e7"), String::from("e5"))));
+                let options = EngineOptions { quiescence: false, check_extensions: false, ..EngineOptions::default() };
+                let search_result = Engine::with_options(options).search(&board, 3);
+
+                assert_eq!(search_result.pv.first(), search_result.best_move.as_ref());
+                assert_eq!(search_result.pv.len(), 3);
+                assert_eq!(search_result.depth, 3);
+                assert_eq!(search_result.seldepth, 3);
+                assert!(search_result.nodes > 0);
+            }
+
+            #[test]
+            fn its_principal_variation_is_playable() {
+                let board_string = String::from("00000000000rnbqkbnr00pppppppp00--------00--------00--------00--------00PPPPPPPP00RNBQKBNR00000000000");
+                let board: Board = Board::new(board_string, Color::White);
+                let search_result = Eval::new(&board, 3);
+                let mut next_board = board.clone();
+
+                for pv_move in search_result.pv.iter() {
+                    let legal_moves = get_all_legal_moves(&next_board);
+                    assert!(legal_moves.contains(pv_move));
+                    next_board.make_move(*pv_move);
+                }
+            }
+
+            #[test]
+            fn it_reports_mate_for_the_side_delivering_it() {
+                let board_string = String::from("00000000000--------00-----K-k00--------00--------00--------00--------00--------00R-------00000000000");
+                let board: Board = Board::new(board_string, Color::White);
+                let search_result = Eval::new(&board, 2);
+
+                assert_eq!(search_result.score, Score::Mate(1));
+                assert_eq!(search_result.best_move, Some(Move::from_chess_move((String::from("a1"), String::from("h1")))));
+            }
+
+            #[test]
+            fn it_still_returns_a_move_when_facing_forced_mate() {
+                let board_string = String::from("00000000000-------k00-----K--00--------00--------00--------00--------00--------00R-------00000000000");
+                let board: Board = Board::new(board_string, Color::Black);
+                let search_result = Eval::new(&board, 3);
+
+                assert_eq!(search_result.score, Score::Mate(-1));
+                assert_eq!(search_result.best_move, Some(Move::from_chess_move((String::from("h8"), String::from("h7")))));
+            }
+
+            #[test]
+            fn it_scores_stalemate_as_a_draw() {
+                let board_string = String::from("00000000000-------k00-----K--00------Q-00--------00--------00--------00--------00--------00000000000");
+                let board: Board = Board::new(board_string, Color::Black);
+                let search_result = Eval::new(&board, 2);
+
+                assert_eq!(search_result.best_move, None);
+                assert_eq!(search_result.score, Score::Centipawns(0));
+            }
+
+            #[test]
+            fn it_widens_the_aspiration_window_until_the_value_fits() {
+                let board_string = String::from("00000000000rnbqkbnr00pppppppp00--------00--------00--------00--------00PPPPPPPP00RNBQKBNR00000000000");
+                let mut board: Board = Board::new(board_string, Color::White);
+                board.make_move(Move::from_chess_move((String::from("d2"), String::from("d4"))));
+                board.make_move(Move::from_chess_move((String::from("e7"), String::from("e5"))));
+                //Pruning decisions depend on the window, so only plain alpha-beta is bound to agree exactly
+                let options = EngineOptions {
+                    null_move_pruning: false,
+                    late_move_reductions: false,
+                    futility_pruning: false,
+                    reverse_futility_pruning: false,
+                    ..EngineOptions::default()
+                };
+                let stop_flag = AtomicBool::new(false);
+                let full_window_cache = TranspositionTable::new(1 << 16);
+                let mut full_window_searcher = Searcher::new(&full_window_cache, &stop_flag, options, MoveHistory::new(), ClassicalEvaluator::new(), None);
+                let aspiration_cache = TranspositionTable::new(1 << 16);
+                let mut aspiration_searcher = Searcher::new(&aspiration_cache, &stop_flag, options, MoveHistory::new(), ClassicalEvaluator::new(), None);
+
+                let (full_window_value, _) = full_window_searcher.aspiration_search(&board, 4, None);
+                let (aspiration_value, _) = aspiration_searcher.aspiration_search(&board, 4, Some(full_window_value - 1000));
+                assert_eq!(aspiration_value, full_window_value);
+            }
+        }
+
+        mod engine {
+            use super::*;
+
+            #[test]
+            fn it_reuses_its_cache_across_searches() {
+                let board_string = String::from("00000000000rnbqkbnr00pppppppp00--------00--------00--------00--------00PPPPPPPP00RNBQKBNR00000000000");
+                let mut board: Board = Board::new(board_string, Color::White);
+                board.make_move(Move::from_chess_move((String::from("d2"), String::from("d4"))));
+                board.make_move(Move::from_chess_move((String::from("e7"), String::from("e5"))));
+                let mut engine = Engine::new();
+                let first_result = engine.search(&board, 3);
+                let second_result = engine.search(&board, 3);
+
+                assert_eq!(first_result.best_move, second_result.best_move);
+                assert_eq!(first_result.score, second_result.score);
+                assert!(second_result.nodes < first_result.nodes);
+            }
+
+            #[test]
+            fn it_keeps_its_cache_valid_for_the_other_side() {
+                let board_string = String::from("00000000000rnbqkbnr00pppppppp00--------00--------00--------00--------00PPPPPPPP00RNBQKBNR00000000000");
+                let mut board: Board = Board::new(board_string, Color::White);
+                board.make_move(Move::from_chess_move((String::from("d2"), String::from("d4"))));
+                board.make_move(Move::from_chess_move((String::from("e7"), String::from("e5"))));
+                board.make_move(Move::from_chess_move((String::from("h2"), String::from("h4"))));
+                board.make_move(Move::from_chess_move((String::from("h7"), String::from("h5"))));
+                let mut engine = Engine::new();
+                engine.search(&board, 3);
+                board.make_move(Move::from_chess_move((String::from("c1"), String::from("g5"))));
+                let reused_result = engine.search(&board, 3);
+                let fresh_result = Eval::new(&board, 3);
+
+                assert_eq!(reused_result.best_move, fresh_result.best_move);
+                assert_eq!(reused_result.score, fresh_result.score);
+            }
+
+            #[test]
+            fn it_stops_searching_when_asked_from_another_thread() {
+                let board_string = String::from("00000000000rnbqkbnr00pppppppp00--------00--------00--------00--------00PPPPPPPP00RNBQKBNR00000000000");
+                let board: Board = Board::new(board_string, Color::White);
+                let mut engine = Engine::new();
+                let stop_handle = engine.stop_handle();
+                let stopper = std::thread::spawn(move || {
+                    std::thread::sleep(Duration::from_millis(200));
+                    stop_handle.stop();
+                });
+                let search_result = engine.search(&board, 30);
+                stopper.join().unwrap();
+
+                assert!(search_result.best_move.is_some());
+                assert!(search_result.depth < 30);
+                assert_eq!(search_result.pv.first(), search_result.best_move.as_ref());
+            }
+
+            #[test]
+            fn it_keeps_a_stop_asked_for_before_the_search() {
+                let board_string = String::from("00000000000rnbqkbnr00pppppppp00--------00--------00--------00--------00PPPPPPPP00RNBQKBNR00000000000");
+                let board: Board = Board::new(board_string, Color::White);
+                let mut engine = Engine::new();
+                engine.stop_handle().stop();
+                let stopped_result = engine.search(&board, 30);
+
+                //Only the first iteration, which cannot be stopped, is searched
+                assert_eq!(stopped_result.depth, 1);
+                assert!(stopped_result.best_move.is_some());
+                assert!(!engine.stop_handle().is_stopped());
+                assert_eq!(engine.search(&board, 2).depth, 2);
+            }
+
+            #[test]
+            fn it_can_be_shared_between_threads() {
+                fn assert_send_and_sync<T: Send + Sync>() {}
+                assert_send_and_sync::<Board>();
+                assert_send_and_sync::<Engine>();
+                assert_send_and_sync::<StopHandle>();
+            }
+
+            #[test]
+            fn it_is_deterministic_with_a_single_thread() {
+                let board_string = String::from("00000000000rnbqkbnr00pppppppp00--------00--------00--------00--------00PPPPPPPP00RNBQKBNR00000000000");
+                let board: Board = Board::new(board_string, Color::White);
+                let first_result = Engine::new().search(&board, 3);
+                let second_result = Engine::new().search(&board, 3);
+
+                assert_eq!(first_result.best_move, second_result.best_move);
+                assert_eq!(first_result.pv, second_result.pv);
+                assert_eq!(first_result.nodes, second_result.nodes);
+            }
+
+            #[test]
+            fn it_searches_with_helper_threads() {
+                let board_string = String::from("00000000000rnbqkbnr00pppppppp00--------00--------00--------00--------00PPPPPPPP00RNBQKBNR00000000000");
+                let mut board: Board = Board::new(board_string, Color::White);
+                board.make_move(Move::from_chess_move((String::from("d2"), String::from("d4"))));
+                board.make_move(Move::from_chess_move((String::from("e7"), String::from("e5"))));
+                board.make_move(Move::from_chess_move((String::from("h2"), String::from("h4"))));
+                board.make_move(Move::from_chess_move((String::from("h7"), String::from("h5"))));
+                board.make_move(Move::from_chess_move((String::from("c1"), String::from("g5"))));
+                let options = EngineOptions { threads: 4, ..EngineOptions::default() };
+                let mut engine = Engine::with_options(options);
+                let search_result = engine.search(&board, 3);
+
+                assert_eq!(search_result.depth, 3);
+                assert!(get_all_legal_moves(&board).contains(&search_result.best_move.unwrap()));
+                assert_eq!(search_result.pv.first(), search_result.best_move.as_ref());
+                //Stopping the helpers is not a stop of the search
+                assert!(!engine.stop_handle().is_stopped());
+                assert_eq!(engine.search(&board, 3).depth, 3);
+            }
+
+            #[test]
+            fn it_clears_its_cache_on_new_game() {
+                let board_string = String::from("00000000000rnbqkbnr00pppppppp00--------00--------00--------00--------00PPPPPPPP00RNBQKBNR00000000000");
+                let board: Board = Board::new(board_string, Color::White);
+                let mut engine = Engine::new();
+                let first_result = engine.search(&board, 3);
+                engine.new_game();
+                let second_result = engine.search(&board, 3);
+
+                assert_eq!(first_result.nodes, second_result.nodes);
+            }
+
+            #[test]
+            fn it_plays_book_moves_up_to_the_book_depth() {
+                use opening_book::{ PolyglotKeys, BookEntry, encode_move };
+                let board = Board::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap();
+                let keys = PolyglotKeys::default();
+                let key = keys.get_hash(&board);
+                let book_move = Move::from_chess_move((String::from("c2"), String::from("c4")));
+                let entries = vec![BookEntry { key, chess_move: encode_move(book_move), weight: 1, learn: 0 }];
+                let mut engine = Engine::with_options(EngineOptions { book_depth: 1, ..EngineOptions::default() });
+                engine.set_opening_book(Some(OpeningBook::new(keys, entries)));
+                let book_result = engine.search(&board, 3);
+
+                assert_eq!(book_result.best_move, Some(book_move));
+                assert_eq!(book_result.score, Score::Book);
+                assert_eq!(book_result.nodes, 0);
+                let mut board = board;
+                board.ply = 1;
+                assert!(engine.search(&board, 3).nodes > 0);
+            }
+
+            #[test]
+            fn it_replays_the_book_choices_of_a_seed() {
+                use opening_book::{ PolyglotKeys, BookEntry, encode_move };
+                let board = Board::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap();
+                let keys = PolyglotKeys::default();
+                let key = keys.get_hash(&board);
+                let entries: Vec<BookEntry> = [("c2", "c4"), ("d2", "d4"), ("e2", "e4"), ("g1", "f3")].iter()
+                    .map(|(from, to)| Move::from_chess_move((String::from(*from), String::from(*to))))
+                    .map(|book_move| BookEntry { key, chess_move: encode_move(book_move), weight: 1, learn: 0 })
+                    .collect();
+                let get_book_moves = |seed: u64| {
+                    let mut engine = Engine::new();
+                    engine.set_opening_book(Some(OpeningBook::new(PolyglotKeys::default(), entries.clone())));
+                    engine.set_book_seed(seed);
+                    (0..16).map(|_| engine.search(&board, 1).best_move.unwrap().to).collect::<Vec<usize>>()
+                };
+
+                assert_eq!(get_book_moves(3), get_book_moves(3));
+                assert_ne!(get_book_moves(3), get_book_moves(4));
+            }
+        }
+
+        mod tablebase_probing {
+            use super::*;
+            use tablebase::{ get_syzygy_name, RookEndgameTablebase };
+
+            #[test]
+            fn it_plays_the_tablebase_move_at_the_root() {
+                let board = Board::from_fen("8/8/8/8/8/8/4k3/3R3K w - - 0 1").unwrap();
+                let mut engine = Engine::new();
+                engine.set_tablebase(Some(Arc::new(RookEndgameTablebase)));
+                let search_result = engine.search(&board, 3);
+
+                assert_eq!(search_result.nodes, 0);
+                assert_eq!(search_result.score, Score::Centipawns(TABLEBASE_WIN_VALUE));
+                assert_eq!(get_syzygy_name(&board.test_move(search_result.best_move.unwrap())), "KvKR");
+            }
+
+            #[test]
+            fn it_cuts_the_search_off_at_known_positions() {
+                let board = Board::from_fen("8/8/8/4k3/8/8/4n3/4R2K w - - 0 1").unwrap();
+                let mut engine = Engine::new();
+                engine.set_tablebase(Some(Arc::new(RookEndgameTablebase)));
+                let search_result = engine.search(&board, 2);
+
+                assert_eq!(search_result.best_move, Some(Move::from_chess_move((String::from("e1"), String::from("e2")))));
+                assert_eq!(search_result.score, Score::Centipawns(TABLEBASE_WIN_VALUE));
+                assert_ne!(Engine::new().search(&board, 2).score, Score::Centipawns(TABLEBASE_WIN_VALUE));
+            }
+        }
+
+        mod selective_search {
+            use super::*;
+
+            fn get_options_without_pruning() -> EngineOptions {
+                EngineOptions {
+                    null_move_pruning: false,
+                    late_move_reductions: false,
+                    futility_pruning: false,
+                    reverse_futility_pruning: false,
+                    ..EngineOptions::default()
+                }
+            }
+
+            #[test]
+            fn it_searches_fewer_nodes_with_pruning_switched_on() {
+                let board_string = String::from("00000000000rnbqkbnr00pppppppp00--------00--------00--------00--------00PPPPPPPP00RNBQKBNR00000000000");
+                let board: Board = Board::new(board_string, Color::White);
+                let pruned_result = Engine::new().search(&board, 5);
+                let full_result = Engine::with_options(get_options_without_pruning()).search(&board, 5);
+
+                assert!(pruned_result.nodes < full_result.nodes);
+            }
+
+            #[test]
+            fn it_finds_the_same_mate_with_pruning_switched_on_or_off() {
+                let board_string = String::from("00000000000--------00-----K-k00--------00--------00--------00--------00--------00R-------00000000000");
+                let board: Board = Board::new(board_string, Color::White);
+                let pruned_result = Engine::new().search(&board, 4);
+                let full_result = Engine::with_options(get_options_without_pruning()).search(&board, 4);
+
+                assert_eq!(pruned_result.score, Score::Mate(1));
+                assert_eq!(full_result.score, Score::Mate(1));
+                assert_eq!(pruned_result.best_move, full_result.best_move);
+            }
+
+            #[test]
+            fn it_only_passes_the_turn_with_pieces_left() {
+                let board_string = String::from("00000000000----k---00---p----00--------00--------00--------00--------00---P----00---NK---00000000000");
+                let board: Board = Board::new(board_string, Color::White);
+
+                assert!(has_non_pawn_material(&board, Color::White));
+                assert!(!has_non_pawn_material(&board, Color::Black));
+            }
+
+            #[test]
+            fn it_reduces_late_moves_more_while_leaving_a_ply_to_search() {
+                assert!(get_late_move_reduction(8, 30) > get_late_move_reduction(8, 4));
+                assert!(get_late_move_reduction(12, 10) > get_late_move_reduction(3, 10));
+                assert_eq!(get_late_move_reduction(3, 60), 1);
+            }
+        }
+
+        mod extensions {
+            use super::*;
+
+            fn assert_mate_in(board_string: &str, depth: u32, moves_to_mate: i32, best_move: (&str, &str)) {
+                let board: Board = Board::new(String::from(board_string), Color::White);
+                let search_result = Eval::new(&board, depth);
+
+                assert_eq!(search_result.score, Score::Mate(moves_to_mate));
+                assert_eq!(search_result.best_move, Some(Move::from_chess_move((String::from(best_move.0), String::from(best_move.1)))));
+            }
+
+            #[test]
+            fn it_finds_a_mate_in_one() {
+                assert_mate_in("00000000000----k---00--------00----K---00--------00--------00--------00--------00-------R00000000000", 1, 1, ("h1", "h8"));
+            }
+
+            #[test]
+            fn it_finds_a_quiet_mate_in_two() {
+                assert_mate_in("00000000000-------k00---R--R-00--------00--------00--------00--------00--------00------K-00000000000", 3, 2, ("g7", "e7"));
+            }
+
+            #[test]
+            fn it_extends_checks_and_forced_replies_to_find_a_mate_in_two() {
+                assert_mate_in("00000000000--r---k-00-----ppp00--------00--------00--------00--------00----QPPP00----R-K-00000000000", 2, 2, ("e2", "e8"));
+            }
+
+            #[test]
+            fn it_misses_the_mate_in_two_without_extensions() {
+                let board: Board = Board::new(String::from("00000000000--r---k-00-----ppp00--------00--------00--------00--------00----QPPP00----R-K-00000000000"), Color::White);
+                let options = EngineOptions { check_extensions: false, ..EngineOptions::default() };
+                let search_result = Engine::with_options(options).search(&board, 2);
+
+                assert_ne!(search_result.score, Score::Mate(2));
+            }
+
+            #[test]
+            fn it_extends_checks_and_forced_replies_to_find_a_mate_in_three() {
+                assert_mate_in("00000000000--rr--k-00-----ppp00--------00--------00----Q---00----R---00-----PPP00----R-K-00000000000", 2, 3, ("e4", "e8"));
+            }
+        }
+
+        mod evaluate {
+            use super::*;
+
+            #[test]
+            fn it_evaluates_from_the_side_to_moves_perspective() {
+                let board_string = String::from("00000000000----k---00--------00---P----00--------00--------00--------00--------00----K---00000000000");
+                let white_board: Board = Board::new(board_string.clone(), Color::White);
+                let black_board: Board = Board::new(board_string, Color::Black);
+
+                assert!(evaluate(&white_board) > 0);
+                assert_eq!(evaluate(&black_board), -evaluate(&white_board));
+            }
+
+            #[test]
+            fn it_counts_the_pawn_structure() {
+                let board_string = String::from("00000000000----k---00-----ppp00--------00--------00--------00--------00P-P-P---00----K---00000000000");
+                let board: Board = Board::new(board_string, Color::White);
+                let (white_value, black_value) = get_snapshot_evaluation(&board, &DEFAULT_EVAL_PARAMS);
+
+                assert!(evaluate(&board) < white_value as i32 - black_value as i32);
+            }
+        }
+
         mod get_white_evaluation {
             use super::*;
 
+            #[test]
+            fn it_centralizes_the_king_once_the_pieces_are_gone() {
+                let board_string = String::from("00000000000----k---00--------00--------00--------00--------00--------00----P---00------K-00000000000");
+                let castled_board: Board = Board::new(board_string, Color::White);
+                let board_string = String::from("00000000000----k---00--------00--------00--------00----K---00--------00----P---00--------00000000000");
+                let central_board: Board = Board::new(board_string, Color::White);
+
+                assert_eq!(get_game_phase(&central_board), 0);
+                assert!(get_white_evaluation(&central_board, &DEFAULT_EVAL_PARAMS) > get_white_evaluation(&castled_board, &DEFAULT_EVAL_PARAMS));
+            }
+
+            #[test]
+            fn it_keeps_the_king_castled_with_the_pieces_on() {
+                let board_string = String::from("00000000000rnbqkbnr00pppppppp00--------00--------00--------00--------00PPPPPPPP00RNBQ-RK-00000000000");
+                let castled_board: Board = Board::new(board_string, Color::White);
+                let board_string = String::from("00000000000rnbqkbnr00pppppppp00--------00--------00--------00----K---00PPPPPPPP00RNBQ-R--00000000000");
+                let central_board: Board = Board::new(board_string, Color::White);
+
+                assert_eq!(get_game_phase(&castled_board), MAX_GAME_PHASE - KNIGHT_PHASE - BISHOP_PHASE);
+                assert!(get_white_evaluation(&castled_board, &DEFAULT_EVAL_PARAMS) > get_white_evaluation(&central_board, &DEFAULT_EVAL_PARAMS));
+            }
+
+            #[test]
+            fn it_blends_the_tables_by_game_phase() {
+                assert_eq!(taper(100, 0, MAX_GAME_PHASE), 100);
+                assert_eq!(taper(100, 0, 0), 0);
+                assert_eq!(taper(100, 0, MAX_GAME_PHASE / 2), 50);
+            }
+
             #[test]
             fn it_gets_the_value_for_whites_starting_position() {
                 let board_string = String::from("00000000000rnbqkbnr00pppppppp00--------00--------00--------00--------00PPPPPPPP00RNBQKBNR00000000000");
                 let board: Board = Board::new(board_string, Color::White);
-                let white_value: u32 = get_white_evaluation(&board);
+                let white_value: u32 = get_white_evaluation(&board, &DEFAULT_EVAL_PARAMS);
                 assert_eq!(white_value, 14420);
             }
 
@@ -427,7 +1558,7 @@ mod tests {
             fn it_gets_the_value_for_blacks_starting_position() {
                 let board_string = String::from("00000000000rnbqkbnr00pppppppp00--------00--------00--------00--------00PPPPPPPP00RNBQKBNR00000000000");
                 let board: Board = Board::new(board_string, Color::White);
-                let black_value: u32 = get_black_evaluation(&board);
+                let black_value: u32 = get_black_evaluation(&board, &DEFAULT_EVAL_PARAMS);
                 assert_eq!(black_value, 14420);
             }
         }
@@ -471,14 +1602,8 @@ mod tests {
             board.make_move(Move::from_chess_move((String::from("d7"), String::from("d5"))));
             board.make_move(Move::from_chess_move((String::from("d1"), String::from("h5"))));
             let legal_moves = get_all_legal_moves(&board);
-            let legal_moves_include_f7_pawn = match legal_moves.iter().find(|legal_move| match legal_move.from {
-                26 => true,
-                _ => false,
-            }) {
-                Some(_) => true,
-                None => false,
-            };
-            assert_eq!(legal_moves_include_f7_pawn, false);
+            let legal_moves_include_f7_pawn = legal_moves.iter().any(|legal_move| legal_move.from == 26);
+            assert!(!legal_moves_include_f7_pawn);
         }
     }
 }
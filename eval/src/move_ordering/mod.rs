@@ -0,0 +1,439 @@
+use std::cmp;
+use std::mem;
+use board::Move;
+use board::Board;
+use board::Piece;
+use board::PieceType;
+use board::square::Color;
+use super::see::see;
+
+//Bound on history scores; the gravity update keeps every entry within it
+const MAX_HISTORY_SCORE: i32 = 16384;
+const MAX_HISTORY_BONUS: i32 = 1200;
+
+//Quiet-move statistics gathered from beta cutoffs: killer moves per ply, a butterfly history
+//table per color and a counter move for each previous move
+#[derive(Clone)]
+pub struct MoveHistory {
+    killers: Vec<[Option<Move>; 2]>,
+    history_scores: Vec<i32>,
+    counter_moves: Vec<Option<Move>>,
+}
+
+impl Default for MoveHistory {
+    fn default() -> MoveHistory {
+        MoveHistory::new()
+    }
+}
+
+impl MoveHistory {
+    pub fn new() -> MoveHistory {
+        MoveHistory {
+            killers: Vec::new(),
+            history_scores: vec![0; 2 * 100 * 100],
+            counter_moves: vec![None; 12 * 100],
+        }
+    }
+
+    pub fn clear(&mut self) {
+        *self = MoveHistory::new();
+    }
+
+    //Killers are tied to plies of the previous search, while history scores only lose weight
+    pub fn age(&mut self) {
+        self.killers.clear();
+        self.history_scores.iter_mut().for_each(|score| *score /= 2);
+    }
+
+    pub fn get_killers(&self, ply: usize) -> [Option<Move>; 2] {
+        match self.killers.get(ply) {
+            Some(killers) => *killers,
+            None => [None, None],
+        }
+    }
+
+    pub fn get_history_score(&self, color: Color, quiet_move: &Move) -> i32 {
+        self.history_scores[get_history_index(color, quiet_move)]
+    }
+
+    pub fn get_counter_move(&self, board: &Board, previous_move: Option<Move>) -> Option<Move> {
+        self.counter_moves[get_counter_move_index(board, previous_move?)?]
+    }
+
+    //Rewards the quiet move that caused a cutoff and penalises the quiet moves tried before it
+    pub fn update_on_cutoff(&mut self, board: &Board, ply: usize, depth: u32, cutoff_move: Move, previous_move: Option<Move>, tried_quiet_moves: &[Move]) {
+        let color = board.current_turn.color;
+        let bonus = cmp::min(depth as i32 * depth as i32 * 16, MAX_HISTORY_BONUS);
+        self.apply_history_bonus(color, &cutoff_move, bonus);
+        tried_quiet_moves.iter()
+            .filter(|tried_move| **tried_move != cutoff_move)
+            .for_each(|tried_move| self.apply_history_bonus(color, tried_move, -bonus));
+
+        if self.killers.len() <= ply {
+            self.killers.resize(ply + 1, [None, None]);
+        }
+        if self.killers[ply][0] != Some(cutoff_move) {
+            self.killers[ply][1] = self.killers[ply][0];
+            self.killers[ply][0] = Some(cutoff_move);
+        }
+
+        if let Some(counter_move_index) = previous_move.and_then(|previous_move| get_counter_move_index(board, previous_move)) {
+            self.counter_moves[counter_move_index] = Some(cutoff_move);
+        }
+    }
+
+    //History gravity: the closer an entry is to the bound, the less a bonus of the same sign moves it
+    fn apply_history_bonus(&mut self, color: Color, quiet_move: &Move, bonus: i32) {
+        let score = &mut self.history_scores[get_history_index(color, quiet_move)];
+        *score += bonus - *score * bonus.abs() / MAX_HISTORY_SCORE;
+    }
+}
+
+fn get_history_index(color: Color, quiet_move: &Move) -> usize {
+    let color_offset = match color {
+        Color::White => 0,
+        Color::Black => 100 * 100,
+    };
+    color_offset + quiet_move.from * 100 + quiet_move.to
+}
+
+//Counter moves are keyed on the piece that just moved and the square it moved to
+fn get_counter_move_index(board: &Board, previous_move: Move) -> Option<usize> {
+    let piece = board.get_piece_at(previous_move.to)?;
+    let color_offset = match piece.color {
+        Color::White => 0,
+        Color::Black => 6,
+    };
+    Some((color_offset + piece.piece_type as usize) * 100 + previous_move.to)
+}
+
+#[derive(Copy, Clone)]
+#[derive(Debug)]
+#[derive(PartialEq)]
+enum Stage {
+    HashMove, GenerateCaptures, Captures, Refutations, GenerateQuietMoves, QuietMoves, LosingCaptures, Done
+}
+
+//Hands out pseudo-legal moves one stage at a time, so that a cutoff on the hash move or a
+//capture spares generating and ordering the quiet moves
+pub struct MovePicker {
+    stage: Stage,
+    hash_move: Option<Move>,
+    //Captures that give up material according to SEE, tried after the quiet moves
+    losing_captures: Vec<(Move, i32)>,
+    //Killer moves followed by the counter move, tried before the remaining quiet moves
+    refutations: Vec<Move>,
+    refutation_index: usize,
+    scored_moves: Vec<(Move, i32)>,
+}
+
+impl MovePicker {
+    pub fn new(board: &Board, hash_move: Option<Move>, move_history: &MoveHistory, ply: usize, previous_move: Option<Move>) -> MovePicker {
+        let hash_move = hash_move.filter(|hash_move| is_pseudo_legal(board, hash_move));
+        let [first_killer, second_killer] = move_history.get_killers(ply);
+        let mut refutations: Vec<Move> = Vec::new();
+        for refutation in [first_killer, second_killer, move_history.get_counter_move(board, previous_move)].iter().flatten() {
+            let is_new = Some(*refutation) != hash_move && !refutations.contains(refutation);
+            if is_new && board.get_piece_at(refutation.to).is_none() && is_pseudo_legal(board, refutation) {
+                refutations.push(*refutation);
+            }
+        }
+        MovePicker {
+            stage: Stage::HashMove,
+            hash_move,
+            losing_captures: Vec::new(),
+            refutations,
+            refutation_index: 0,
+            scored_moves: Vec::new(),
+        }
+    }
+
+    pub fn next_move(&mut self, board: &Board, move_history: &MoveHistory) -> Option<Move> {
+        loop {
+            match self.stage {
+                Stage::HashMove => {
+                    self.stage = Stage::GenerateCaptures;
+                    if self.hash_move.is_some() {
+                        return self.hash_move;
+                    }
+                },
+                Stage::GenerateCaptures => {
+                    let (winning_captures, losing_captures) = get_pseudo_legal_captures(board).into_iter()
+                        .partition(|capture| see(board, capture) >= 0);
+                    self.scored_moves = score_by_mvv_lva(board, winning_captures);
+                    self.losing_captures = score_by_mvv_lva(board, losing_captures);
+                    self.stage = Stage::Captures;
+                },
+                Stage::Captures => match self.pick_best_scored_move() {
+                    Some(capture) => return Some(capture),
+                    None => self.stage = Stage::Refutations,
+                },
+                Stage::Refutations => match self.refutations.get(self.refutation_index) {
+                    Some(refutation) => {
+                        self.refutation_index += 1;
+                        return Some(*refutation);
+                    },
+                    None => self.stage = Stage::GenerateQuietMoves,
+                },
+                Stage::GenerateQuietMoves => {
+                    let color = board.current_turn.color;
+                    self.scored_moves = get_pseudo_legal_quiet_moves(board).into_iter()
+                        .map(|quiet_move| (quiet_move, move_history.get_history_score(color, &quiet_move)))
+                        .collect();
+                    self.stage = Stage::QuietMoves;
+                },
+                Stage::QuietMoves => match self.pick_best_scored_move() {
+                    Some(quiet_move) => return Some(quiet_move),
+                    None => {
+                        self.scored_moves = mem::take(&mut self.losing_captures);
+                        self.stage = Stage::LosingCaptures;
+                    },
+                },
+                Stage::LosingCaptures => match self.pick_best_scored_move() {
+                    Some(capture) => return Some(capture),
+                    None => self.stage = Stage::Done,
+                },
+                Stage::Done => return None,
+            }
+        }
+    }
+
+    //Selection sort one move at a time; most nodes only ever look at the first few moves.
+    //Ties go to the earliest generated move, and no move is handed out twice
+    fn pick_best_scored_move(&mut self) -> Option<Move> {
+        loop {
+            let best_index = self.scored_moves.iter()
+                .enumerate()
+                .fold(None, |best: Option<(usize, i32)>, (i, &(_, score))| match best {
+                    Some((_, best_score)) if best_score >= score => best,
+                    _ => Some((i, score)),
+                })?
+                .0;
+            let (best_move, _) = self.scored_moves.remove(best_index);
+            if Some(best_move) != self.hash_move && !self.refutations.contains(&best_move) {
+                return Some(best_move);
+            }
+        }
+    }
+}
+
+pub fn get_pseudo_legal_captures(board: &Board) -> Vec<Move> {
+    get_side_to_move_moves(board, Piece::get_pseudo_legal_captures)
+}
+
+pub fn get_pseudo_legal_quiet_moves(board: &Board) -> Vec<Move> {
+    get_side_to_move_moves(board, Piece::get_pseudo_legal_quiet_moves)
+}
+
+fn get_side_to_move_moves(board: &Board, get_piece_moves: fn(&Piece, usize, &Board) -> Vec<Move>) -> Vec<Move> {
+    board.squares.iter()
+        .enumerate()
+        .filter_map(|(i, square)| match square.piece {
+            Some(p) if p.color == board.current_turn.color => Some((i, p)),
+            _ => None,
+        })
+        .flat_map(|(i, piece)| get_piece_moves(&piece, i, board))
+        .collect()
+}
+
+fn score_by_mvv_lva(board: &Board, captures: Vec<Move>) -> Vec<(Move, i32)> {
+    captures.into_iter()
+        .map(|capture| (capture, get_mvv_lva_score(board, &capture)))
+        .collect()
+}
+
+//Captures that do not lose material according to SEE, most valuable victim first. Quiescence
+//search looks at nothing else
+pub fn get_winning_captures(board: &Board) -> Vec<Move> {
+    let mut scored_captures = score_by_mvv_lva(board, get_pseudo_legal_captures(board).into_iter()
+        .filter(|capture| see(board, capture) >= 0)
+        .collect());
+    scored_captures.sort_by_key(|&(_, score)| cmp::Reverse(score));
+    scored_captures.into_iter().map(|(capture, _)| capture).collect()
+}
+
+//Most valuable victim first, and among those the least valuable attacker
+pub fn get_mvv_lva_score(board: &Board, capture: &Move) -> i32 {
+    let victim_rank = match board.get_piece_at(capture.to) {
+        Some(p) => get_piece_rank(p.piece_type),
+        None => 0,
+    };
+    let attacker_rank = match board.get_piece_at(capture.from) {
+        Some(p) => get_piece_rank(p.piece_type),
+        None => 0,
+    };
+    victim_rank * 10 - attacker_rank
+}
+
+fn get_piece_rank(piece_type: PieceType) -> i32 {
+    match piece_type {
+        PieceType::Pawn => 1,
+        PieceType::Knight => 2,
+        PieceType::Bishop => 3,
+        PieceType::Rook => 4,
+        PieceType::Queen => 5,
+        PieceType::King => 6,
+    }
+}
+
+//Cache entries can collide, so a hash move is only trusted if the position could produce it
+fn is_pseudo_legal(board: &Board, chess_move: &Move) -> bool {
+    match board.get_piece_at(chess_move.from) {
+        Some(p) if p.color == board.current_turn.color => p.get_pseudo_legal_moves(chess_move.from, board).contains(chess_move),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use board::square::Color;
+
+    fn collect_moves(board: &Board, hash_move: Option<Move>) -> Vec<Move> {
+        collect_moves_with_history(board, hash_move, &MoveHistory::new(), 0, None)
+    }
+
+    fn collect_moves_with_history(board: &Board, hash_move: Option<Move>, move_history: &MoveHistory, ply: usize, previous_move: Option<Move>) -> Vec<Move> {
+        let mut move_picker = MovePicker::new(board, hash_move, move_history, ply, previous_move);
+        let mut moves = Vec::new();
+        while let Some(next_move) = move_picker.next_move(board, move_history) {
+            moves.push(next_move);
+        }
+        moves
+    }
+
+    mod move_picker {
+        use super::*;
+
+        #[test]
+        fn it_hands_out_every_pseudo_legal_move_once() {
+            let board_string = String::from("00000000000rnbqkbnr00pppppppp00--------00--------00--------00--------00PPPPPPPP00RNBQKBNR00000000000");
+            let board: Board = Board::new(board_string, Color::White);
+            let hash_move = Move::from_chess_move((String::from("e2"), String::from("e4")));
+            let moves = collect_moves(&board, Some(hash_move));
+
+            assert_eq!(moves.len(), 20);
+            assert_eq!(moves[0], hash_move);
+            assert_eq!(moves.iter().filter(|picked_move| **picked_move == hash_move).count(), 1);
+        }
+
+        #[test]
+        fn it_ignores_a_hash_move_that_does_not_fit_the_position() {
+            let board_string = String::from("00000000000rnbqkbnr00pppppppp00--------00--------00--------00--------00PPPPPPPP00RNBQKBNR00000000000");
+            let board: Board = Board::new(board_string, Color::White);
+            let moves = collect_moves(&board, Some(Move::from_chess_move((String::from("e7"), String::from("e5")))));
+
+            assert_eq!(moves.len(), 20);
+            assert!(!moves.contains(&Move::from_chess_move((String::from("e7"), String::from("e5")))));
+        }
+
+        #[test]
+        fn it_orders_captures_by_most_valuable_victim_then_least_valuable_attacker() {
+            let board_string = String::from("00000000000rnbqkbnr00pppppppp00--------00--------00--------00--------00PPPPPPPP00RNBQKBNR00000000000");
+            let mut board: Board = Board::new(board_string, Color::White);
+            board.make_move(Move::from_chess_move((String::from("e2"), String::from("e4"))));
+            board.make_move(Move::from_chess_move((String::from("d7"), String::from("d5"))));
+            board.make_move(Move::from_chess_move((String::from("d1"), String::from("g4"))));
+            board.make_move(Move::from_chess_move((String::from("c8"), String::from("g4"))));
+            board.make_move(Move::from_chess_move((String::from("b1"), String::from("c3"))));
+            board.make_move(Move::from_chess_move((String::from("g4"), String::from("f5"))));
+            let moves = collect_moves(&board, None);
+
+            assert_eq!(moves[0], Move::from_chess_move((String::from("e4"), String::from("f5"))));
+            assert_eq!(moves[1], Move::from_chess_move((String::from("e4"), String::from("d5"))));
+            assert_eq!(moves[2], Move::from_chess_move((String::from("c3"), String::from("d5"))));
+            assert!(board.get_piece_at(moves[3].to).is_none());
+        }
+    }
+
+    mod see_ordering {
+        use super::*;
+
+        #[test]
+        fn it_tries_captures_that_lose_material_last() {
+            let board_string = String::from("00000000000----k---00--------00--------00-------p00------n-00--------00--------00---QK---00000000000");
+            let board: Board = Board::new(board_string, Color::White);
+            let moves = collect_moves(&board, None);
+
+            assert_eq!(moves.last(), Some(&Move::from_chess_move((String::from("d1"), String::from("g4")))));
+            assert!(get_winning_captures(&board).is_empty());
+        }
+    }
+
+    mod move_history {
+        use super::*;
+
+        #[test]
+        fn it_tries_killer_moves_right_after_the_captures() {
+            let board_string = String::from("00000000000rnbqkbnr00pppppppp00--------00--------00--------00--------00PPPPPPPP00RNBQKBNR00000000000");
+            let board: Board = Board::new(board_string, Color::White);
+            let hash_move = Move::from_chess_move((String::from("e2"), String::from("e4")));
+            let killer_move = Move::from_chess_move((String::from("g1"), String::from("f3")));
+            let mut move_history = MoveHistory::new();
+            move_history.update_on_cutoff(&board, 2, 3, killer_move, None, &[killer_move]);
+            let moves = collect_moves_with_history(&board, Some(hash_move), &move_history, 2, None);
+
+            assert_eq!(moves.len(), 20);
+            assert_eq!(moves[0], hash_move);
+            assert_eq!(moves[1], killer_move);
+            assert_eq!(move_history.get_killers(3), [None, None]);
+        }
+
+        #[test]
+        fn it_tries_the_counter_move_to_the_previous_move() {
+            let board_string = String::from("00000000000rnbqkbnr00pppppppp00--------00--------00--------00--------00PPPPPPPP00RNBQKBNR00000000000");
+            let mut board: Board = Board::new(board_string, Color::White);
+            let previous_move = Move::from_chess_move((String::from("e2"), String::from("e4")));
+            board.make_move(previous_move);
+            let counter_move = Move::from_chess_move((String::from("c7"), String::from("c5")));
+            let mut move_history = MoveHistory::new();
+            move_history.update_on_cutoff(&board, 5, 3, counter_move, Some(previous_move), &[counter_move]);
+
+            assert_eq!(move_history.get_counter_move(&board, Some(previous_move)), Some(counter_move));
+            assert_eq!(collect_moves_with_history(&board, None, &move_history, 1, Some(previous_move))[0], counter_move);
+        }
+
+        #[test]
+        fn it_orders_quiet_moves_by_history_score() {
+            let board_string = String::from("00000000000rnbqkbnr00pppppppp00--------00--------00--------00--------00PPPPPPPP00RNBQKBNR00000000000");
+            let board: Board = Board::new(board_string, Color::White);
+            let good_move = Move::from_chess_move((String::from("d2"), String::from("d4")));
+            let bad_move = Move::from_chess_move((String::from("b1"), String::from("a3")));
+            let mut move_history = MoveHistory::new();
+            move_history.update_on_cutoff(&board, 0, 4, good_move, None, &[bad_move, good_move]);
+            let moves = collect_moves_with_history(&board, None, &move_history, 1, None);
+
+            assert_eq!(moves[0], good_move);
+            assert_eq!(moves[19], bad_move);
+        }
+
+        #[test]
+        fn it_keeps_history_scores_within_bounds() {
+            let board_string = String::from("00000000000rnbqkbnr00pppppppp00--------00--------00--------00--------00PPPPPPPP00RNBQKBNR00000000000");
+            let board: Board = Board::new(board_string, Color::White);
+            let good_move = Move::from_chess_move((String::from("d2"), String::from("d4")));
+            let bad_move = Move::from_chess_move((String::from("b1"), String::from("a3")));
+            let mut move_history = MoveHistory::new();
+            for _ in 0..1000 {
+                move_history.update_on_cutoff(&board, 0, 20, good_move, None, &[bad_move, good_move]);
+            }
+
+            assert!(move_history.get_history_score(Color::White, &good_move) <= MAX_HISTORY_SCORE);
+            assert!(move_history.get_history_score(Color::White, &bad_move) >= -MAX_HISTORY_SCORE);
+        }
+
+        #[test]
+        fn it_halves_history_and_forgets_killers_when_aged() {
+            let board_string = String::from("00000000000rnbqkbnr00pppppppp00--------00--------00--------00--------00PPPPPPPP00RNBQKBNR00000000000");
+            let board: Board = Board::new(board_string, Color::White);
+            let killer_move = Move::from_chess_move((String::from("g1"), String::from("f3")));
+            let mut move_history = MoveHistory::new();
+            move_history.update_on_cutoff(&board, 0, 4, killer_move, None, &[killer_move]);
+            let score = move_history.get_history_score(Color::White, &killer_move);
+            move_history.age();
+
+            assert_eq!(move_history.get_history_score(Color::White, &killer_move), score / 2);
+            assert_eq!(move_history.get_killers(0), [None, None]);
+        }
+    }
+}
@@ -0,0 +1,342 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::sync::Arc;
+use board::Board;
+use board::Move;
+use board::Piece;
+use board::PieceType;
+use board::square::Color;
+use super::evaluator::Evaluator;
+use super::{ MATE_VALUE, MAX_MATE_PLY };
+
+//Weights file layout, all little-endian: the magic bytes, the hidden layer size as a u32, then the
+//feature weights (i16, FEATURE_COUNT rows of hidden_size), the feature biases (i16), the output
+//weights (i16, the side to move's half first) and the output bias (i32)
+const MAGIC: &[u8; 8] = b"CHSSNNUE";
+//Every piece type of either color on every square, as seen from one side
+const FEATURE_COUNT: usize = 2 * 6 * 64;
+//Accumulator values are clipped to 0..=ACCUMULATOR_SCALE before reaching the output layer, whose
+//weights are scaled by OUTPUT_WEIGHT_SCALE; OUTPUT_SCALE turns the result back into centipawns
+const ACCUMULATOR_SCALE: i32 = 255;
+const OUTPUT_WEIGHT_SCALE: i32 = 64;
+const OUTPUT_SCALE: i32 = 400;
+//A network with extreme weights must still never come up with something that looks like a mate score
+const MAX_OUTPUT: i64 = (MATE_VALUE - MAX_MATE_PLY - 1) as i64;
+
+pub struct NnueNetwork {
+    hidden_size: usize,
+    feature_weights: Vec<i16>,
+    feature_biases: Vec<i16>,
+    output_weights: Vec<i16>,
+    output_bias: i32,
+}
+
+impl NnueNetwork {
+    pub fn new(hidden_size: usize, feature_weights: Vec<i16>, feature_biases: Vec<i16>, output_weights: Vec<i16>, output_bias: i32) -> Option<NnueNetwork> {
+        let has_valid_sizes = hidden_size > 0
+            && feature_weights.len() == FEATURE_COUNT * hidden_size
+            && feature_biases.len() == hidden_size
+            && output_weights.len() == 2 * hidden_size;
+        match has_valid_sizes {
+            true => Some(NnueNetwork { hidden_size, feature_weights, feature_biases, output_weights, output_bias }),
+            false => None,
+        }
+    }
+
+    pub fn load(path: &Path) -> io::Result<NnueNetwork> {
+        NnueNetwork::from_bytes(&fs::read(path)?)
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        fs::write(path, self.to_bytes())
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> io::Result<NnueNetwork> {
+        let invalid_data = |message: &str| io::Error::new(io::ErrorKind::InvalidData, format!("Invalid network file: {}", message));
+        if bytes.len() < MAGIC.len() + 4 || &bytes[..MAGIC.len()] != MAGIC {
+            return Err(invalid_data("unknown format"));
+        }
+        let mut position = MAGIC.len();
+        let hidden_size = read_u32(bytes, &mut position) as usize;
+        let expected_length = position + 2 * (FEATURE_COUNT * hidden_size + hidden_size + 2 * hidden_size) + 4;
+        if hidden_size == 0 || bytes.len() != expected_length {
+            return Err(invalid_data("the size does not match the hidden layer size"));
+        }
+
+        let feature_weights = read_i16s(bytes, &mut position, FEATURE_COUNT * hidden_size);
+        let feature_biases = read_i16s(bytes, &mut position, hidden_size);
+        let output_weights = read_i16s(bytes, &mut position, 2 * hidden_size);
+        let output_bias = read_u32(bytes, &mut position) as i32;
+        NnueNetwork::new(hidden_size, feature_weights, feature_biases, output_weights, output_bias)
+            .ok_or_else(|| invalid_data("inconsistent layer sizes"))
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = MAGIC.to_vec();
+        bytes.extend_from_slice(&(self.hidden_size as u32).to_le_bytes());
+        self.feature_weights.iter()
+            .chain(self.feature_biases.iter())
+            .chain(self.output_weights.iter())
+            .for_each(|weight| bytes.extend_from_slice(&weight.to_le_bytes()));
+        bytes.extend_from_slice(&self.output_bias.to_le_bytes());
+        bytes
+    }
+
+    fn get_feature_weights(&self, feature: usize) -> &[i16] {
+        &self.feature_weights[feature * self.hidden_size..(feature + 1) * self.hidden_size]
+    }
+
+    fn refresh(&self, board: &Board) -> Accumulator {
+        let mut accumulator = Accumulator {
+            hash: board.hash,
+            values: [self.feature_biases.clone(), self.feature_biases.clone()],
+        };
+        for (board_index, square) in board.squares.iter().enumerate() {
+            if let Some(piece) = square.piece {
+                self.add_piece(&mut accumulator, piece, board_index);
+            }
+        }
+        accumulator
+    }
+
+    //Wrapping arithmetic keeps incremental updates exactly equal to a refresh even if a sum overflows
+    fn add_piece(&self, accumulator: &mut Accumulator, piece: Piece, board_index: usize) {
+        for perspective in [Color::White, Color::Black].iter() {
+            let weights = self.get_feature_weights(get_feature(piece, board_index, *perspective));
+            let values = &mut accumulator.values[get_color_index(*perspective)];
+            values.iter_mut().zip(weights).for_each(|(value, weight)| *value = value.wrapping_add(*weight));
+        }
+    }
+
+    fn remove_piece(&self, accumulator: &mut Accumulator, piece: Piece, board_index: usize) {
+        for perspective in [Color::White, Color::Black].iter() {
+            let weights = self.get_feature_weights(get_feature(piece, board_index, *perspective));
+            let values = &mut accumulator.values[get_color_index(*perspective)];
+            values.iter_mut().zip(weights).for_each(|(value, weight)| *value = value.wrapping_sub(*weight));
+        }
+    }
+
+    //Plain loops over fixed-size slices, which the compiler vectorizes where the target allows it
+    fn get_output(&self, accumulator: &Accumulator, color: Color) -> i32 {
+        let own_values = &accumulator.values[get_color_index(color)];
+        let enemy_values = &accumulator.values[1 - get_color_index(color)];
+        let (own_weights, enemy_weights) = self.output_weights.split_at(self.hidden_size);
+        let sum = get_weighted_sum(own_values, own_weights) + get_weighted_sum(enemy_values, enemy_weights);
+        let output = (sum + self.output_bias as i64) * OUTPUT_SCALE as i64 / (ACCUMULATOR_SCALE * OUTPUT_WEIGHT_SCALE) as i64;
+        output.clamp(-MAX_OUTPUT, MAX_OUTPUT) as i32
+    }
+}
+
+#[derive(Clone)]
+struct Accumulator {
+    //Position the accumulator was computed for, so that one gone out of step is never used
+    hash: u64,
+    //Hidden layer before activation, once from white's side and once from black's
+    values: [Vec<i16>; 2],
+}
+
+//Keeps one accumulator per ply of the line being searched. Each move updates a copy of the previous
+//accumulator with the few features it changes instead of going over the whole board
+#[derive(Clone)]
+pub struct NnueEvaluator {
+    network: Arc<NnueNetwork>,
+    accumulators: Vec<Accumulator>,
+}
+
+impl NnueEvaluator {
+    pub fn new(network: Arc<NnueNetwork>) -> NnueEvaluator {
+        NnueEvaluator { network, accumulators: Vec::new() }
+    }
+
+    fn get_current_accumulator(&mut self, board: &Board) -> &Accumulator {
+        match self.accumulators.last() {
+            Some(accumulator) if accumulator.hash == board.hash => {},
+            _ => {
+                let accumulator = self.network.refresh(board);
+                self.accumulators.clear();
+                self.accumulators.push(accumulator);
+            },
+        }
+        self.accumulators.last().unwrap()
+    }
+}
+
+impl Evaluator for NnueEvaluator {
+    fn evaluate(&mut self, board: &Board) -> i32 {
+        let color = board.current_turn.color;
+        let network = self.network.clone();
+        let accumulator = self.get_current_accumulator(board);
+        network.get_output(accumulator, color)
+    }
+
+    fn push_move(&mut self, board: &Board, chess_move: Option<Move>, next_board: &Board) {
+        let mut accumulator = self.get_current_accumulator(board).clone();
+        if let Some(chess_move) = chess_move {
+            let piece = board.get_piece_at(chess_move.from)
+                .expect("There is no piece on the square from which the move is being made");
+            if let Some(captured_piece) = board.get_piece_at(chess_move.to) {
+                self.network.remove_piece(&mut accumulator, captured_piece, chess_move.to);
+            }
+            self.network.remove_piece(&mut accumulator, piece, chess_move.from);
+            self.network.add_piece(&mut accumulator, piece, chess_move.to);
+        }
+        accumulator.hash = next_board.hash;
+        self.accumulators.push(accumulator);
+    }
+
+    fn pop_move(&mut self) {
+        self.accumulators.pop();
+    }
+}
+
+//Wide enough that no hidden layer size or weights can overflow it
+fn get_weighted_sum(values: &[i16], weights: &[i16]) -> i64 {
+    values.iter()
+        .zip(weights)
+        .map(|(value, weight)| (*value as i32).clamp(0, ACCUMULATOR_SCALE) as i64 * *weight as i64)
+        .sum()
+}
+
+//Pieces of the perspective's own color come first, and black sees the board upside down
+fn get_feature(piece: Piece, board_index: usize, perspective: Color) -> usize {
+    let rank = 9 - board_index / 10;
+    let file = board_index % 10;
+    let square = (rank - 1) * 8 + file - 1;
+    let (square, color_offset) = match perspective {
+        Color::White => (square, get_color_index(piece.color)),
+        Color::Black => (square ^ 56, 1 - get_color_index(piece.color)),
+    };
+    let piece_index = match piece.piece_type {
+        PieceType::Pawn => 0,
+        PieceType::Knight => 1,
+        PieceType::Bishop => 2,
+        PieceType::Rook => 3,
+        PieceType::Queen => 4,
+        PieceType::King => 5,
+    };
+    (color_offset * 6 + piece_index) * 64 + square
+}
+
+fn get_color_index(color: Color) -> usize {
+    match color {
+        Color::White => 0,
+        Color::Black => 1,
+    }
+}
+
+fn read_u32(bytes: &[u8], position: &mut usize) -> u32 {
+    let value = u32::from_le_bytes([bytes[*position], bytes[*position + 1], bytes[*position + 2], bytes[*position + 3]]);
+    *position += 4;
+    value
+}
+
+fn read_i16s(bytes: &[u8], position: &mut usize, count: usize) -> Vec<i16> {
+    let values = bytes[*position..*position + 2 * count]
+        .chunks(2)
+        .map(|pair| i16::from_le_bytes([pair[0], pair[1]]))
+        .collect();
+    *position += 2 * count;
+    values
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::{ Engine, EngineOptions };
+
+    const HIDDEN_SIZE: usize = 16;
+
+    //Arbitrary but fixed weights, small enough to stay clear of the activation limits
+    fn get_test_network() -> NnueNetwork {
+        let mut seed: u32 = 12345;
+        let mut next_weight = |range: i32| {
+            seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12345);
+            ((seed >> 16) as i32 % (2 * range + 1) - range) as i16
+        };
+        let feature_weights = (0..FEATURE_COUNT * HIDDEN_SIZE).map(|_| next_weight(8)).collect();
+        let feature_biases = (0..HIDDEN_SIZE).map(|_| next_weight(64) + 64).collect();
+        let output_weights = (0..2 * HIDDEN_SIZE).map(|_| next_weight(64)).collect();
+        NnueNetwork::new(HIDDEN_SIZE, feature_weights, feature_biases, output_weights, 100).unwrap()
+    }
+
+    mod nnue_network {
+        use super::*;
+
+        #[test]
+        fn it_reads_back_what_it_writes() {
+            let network = get_test_network();
+            let read_network = NnueNetwork::from_bytes(&network.to_bytes()).unwrap();
+            assert_eq!(read_network.to_bytes(), network.to_bytes());
+        }
+
+        #[test]
+        fn it_keeps_extreme_outputs_below_mate_scores() {
+            let board_string = String::from("00000000000rnbqkbnr00pppppppp00--------00--------00--------00--------00PPPPPPPP00RNBQKBNR00000000000");
+            let board: Board = Board::new(board_string, Color::White);
+            let hidden_size = 512;
+            let get_network = |output_weight: i16, output_bias: i32| {
+                let network = NnueNetwork::new(hidden_size, vec![0; FEATURE_COUNT * hidden_size], vec![i16::MAX; hidden_size], vec![output_weight; 2 * hidden_size], output_bias);
+                Arc::new(network.unwrap())
+            };
+
+            assert_eq!(NnueEvaluator::new(get_network(i16::MAX, i32::MAX)).evaluate(&board), MAX_OUTPUT as i32);
+            assert_eq!(NnueEvaluator::new(get_network(i16::MIN, i32::MIN)).evaluate(&board), -MAX_OUTPUT as i32);
+        }
+
+        #[test]
+        fn it_rejects_truncated_files() {
+            let bytes = get_test_network().to_bytes();
+            assert!(NnueNetwork::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+            assert!(NnueNetwork::from_bytes(b"NOTANNUE").is_err());
+        }
+    }
+
+    mod nnue_evaluator {
+        use super::*;
+
+        #[test]
+        fn it_updates_the_accumulator_incrementally() {
+            let network = Arc::new(get_test_network());
+            let board_string = String::from("00000000000rnbqkbnr00pppppppp00--------00--------00--------00--------00PPPPPPPP00RNBQKBNR00000000000");
+            let mut board: Board = Board::new(board_string, Color::White);
+            let mut evaluator = NnueEvaluator::new(network.clone());
+            evaluator.evaluate(&board);
+
+            let moves = [("e2", "e4"), ("d7", "d5"), ("e4", "d5"), ("d8", "d5")];
+            for (from, to) in moves.iter() {
+                let chess_move = Move::from_chess_move((String::from(*from), String::from(*to)));
+                let next_board = board.test_move(chess_move);
+                evaluator.push_move(&board, Some(chess_move), &next_board);
+                board = next_board;
+                let incremental_accumulator = evaluator.accumulators.last().unwrap().clone();
+                assert_eq!(incremental_accumulator.values, network.refresh(&board).values);
+                assert_eq!(evaluator.evaluate(&board), NnueEvaluator::new(network.clone()).evaluate(&board));
+            }
+            assert_eq!(evaluator.accumulators.len(), moves.len() + 1);
+        }
+
+        #[test]
+        fn it_sees_the_same_position_from_either_side() {
+            let network = Arc::new(get_test_network());
+            let board_string = String::from("00000000000----k---00-----ppp00--------00--------00---N----00--------00PPP-----00-K------00000000000");
+            let white_board: Board = Board::new(board_string, Color::White);
+            let board_string = String::from("00000000000-k------00ppp-----00--------00---n----00--------00--------00-----PPP00----K---00000000000");
+            let mirrored_board: Board = Board::new(board_string, Color::Black);
+
+            let mut evaluator = NnueEvaluator::new(network);
+            assert_eq!(evaluator.evaluate(&white_board), evaluator.evaluate(&mirrored_board));
+        }
+
+        #[test]
+        fn it_searches_with_the_engine() {
+            let board_string = String::from("00000000000rnbqkbnr00pppppppp00--------00--------00--------00--------00PPPPPPPP00RNBQKBNR00000000000");
+            let board: Board = Board::new(board_string, Color::White);
+            let evaluator = NnueEvaluator::new(Arc::new(get_test_network()));
+            let mut engine = Engine::with_evaluator(EngineOptions::default(), evaluator);
+
+            assert!(engine.search(&board, 3).best_move.is_some());
+            assert!(engine.evaluator().accumulators.len() <= 1);
+        }
+    }
+}
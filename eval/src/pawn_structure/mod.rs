@@ -0,0 +1,274 @@
+use board::Board;
+use board::PieceType;
+use board::square::Color;
+use board::square::{ get_enemy_color, get_forward_step, get_relative_rank, has_pawn_on_file, is_pawn };
+use super::MAX_GAME_PHASE;
+
+//Passed pawn bonuses by rank as seen from the pawn's own side
+const PASSED_PAWN_MIDGAME_BONUS: [i32; 9] = [0, 0, 5, 10, 20, 35, 60, 100, 0];
+const PASSED_PAWN_ENDGAME_BONUS: [i32; 9] = [0, 0, 10, 20, 40, 70, 120, 180, 0];
+const ISOLATED_PAWN_PENALTY: i32 = 15;
+const DOUBLED_PAWN_PENALTY: i32 = 15;
+const BACKWARD_PAWN_PENALTY: i32 = 10;
+//Defended by a pawn diagonally behind it, by rank as well since a chain is worth more the further it has come
+const PAWN_CHAIN_BONUS: [i32; 9] = [0, 0, 0, 5, 10, 15, 25, 40, 0];
+//Standing next to a pawn of its own on the same rank
+const CONNECTED_PAWN_BONUS: [i32; 9] = [0, 0, 3, 4, 6, 10, 15, 25, 0];
+
+#[derive(Copy, Clone)]
+struct PawnEntry {
+    pawn_hash: u64,
+    //Every term that depends on nothing but the pawns, from white's perspective
+    structure_value: i32,
+    //One bit per board index
+    white_passed_pawns: u128,
+    black_passed_pawns: u128,
+}
+
+//Pawns move rarely compared to pieces, so most positions in a search share a handful of pawn
+//structures. Each search thread owns its own table
+#[derive(Clone)]
+pub struct PawnHashTable {
+    entries: Vec<Option<PawnEntry>>,
+}
+
+impl PawnHashTable {
+    pub fn new(size: usize) -> PawnHashTable {
+        PawnHashTable { entries: vec![None; size.max(1)] }
+    }
+
+    //From white's perspective
+    pub fn evaluate(&mut self, board: &Board, phase: u32) -> i32 {
+        let index = (board.pawn_hash % self.entries.len() as u64) as usize;
+        let entry = match self.entries[index] {
+            Some(entry) if entry.pawn_hash == board.pawn_hash => entry,
+            _ => {
+                let entry = analyze_pawn_structure(board);
+                self.entries[index] = Some(entry);
+                entry
+            },
+        };
+        get_entry_value(board, &entry, phase)
+    }
+}
+
+//From white's perspective, without a pawn hash table
+pub fn evaluate_pawn_structure(board: &Board, phase: u32) -> i32 {
+    get_entry_value(board, &analyze_pawn_structure(board), phase)
+}
+
+//From the color's own perspective, without a pawn hash table
+pub fn evaluate_pawn_structure_of_color(board: &Board, color: Color, phase: u32) -> i32 {
+    let entry = analyze_pawn_structure(board);
+    let passed_pawns = match color {
+        Color::White => entry.white_passed_pawns,
+        Color::Black => entry.black_passed_pawns,
+    };
+    let structure_value: i32 = board.squares.iter()
+        .enumerate()
+        .filter(|(_, square)| match square.piece {
+            Some(p) => p.piece_type == PieceType::Pawn && p.color == color,
+            None => false,
+        })
+        .map(|(board_index, _)| get_pawn_value(board, board_index, color))
+        .sum();
+    structure_value + get_passed_pawns_value(board, passed_pawns, color, phase)
+}
+
+//Passed pawns are looked at again every time since whether they are blocked depends on the pieces
+fn get_entry_value(board: &Board, entry: &PawnEntry, phase: u32) -> i32 {
+    entry.structure_value
+        + get_passed_pawns_value(board, entry.white_passed_pawns, Color::White, phase)
+        - get_passed_pawns_value(board, entry.black_passed_pawns, Color::Black, phase)
+}
+
+fn get_passed_pawns_value(board: &Board, passed_pawns: u128, color: Color, phase: u32) -> i32 {
+    (0..100)
+        .filter(|board_index| passed_pawns & (1 << board_index) != 0)
+        .map(|board_index| {
+            let rank = get_relative_rank(board_index, color);
+            let bonus = (PASSED_PAWN_MIDGAME_BONUS[rank] * phase as i32
+                + PASSED_PAWN_ENDGAME_BONUS[rank] * (MAX_GAME_PHASE - phase) as i32) / MAX_GAME_PHASE as i32;
+            //A blocked passed pawn is only worth half as much
+            let stop_square = (board_index as i32 + get_forward_step(color)) as usize;
+            match board.get_piece_at(stop_square) {
+                Some(_) => bonus / 2,
+                None => bonus,
+            }
+        })
+        .sum()
+}
+
+fn analyze_pawn_structure(board: &Board) -> PawnEntry {
+    let mut entry = PawnEntry { pawn_hash: board.pawn_hash, structure_value: 0, white_passed_pawns: 0, black_passed_pawns: 0 };
+
+    for (board_index, square) in board.squares.iter().enumerate() {
+        let color = match square.piece {
+            Some(p) if p.piece_type == PieceType::Pawn => p.color,
+            _ => continue,
+        };
+        let value = get_pawn_value(board, board_index, color);
+        let is_passed = is_passed_pawn(board, board_index, color);
+        match color {
+            Color::White => {
+                entry.structure_value += value;
+                if is_passed {
+                    entry.white_passed_pawns |= 1 << board_index;
+                }
+            },
+            Color::Black => {
+                entry.structure_value -= value;
+                if is_passed {
+                    entry.black_passed_pawns |= 1 << board_index;
+                }
+            },
+        }
+    }
+    entry
+}
+
+fn get_pawn_value(board: &Board, board_index: usize, color: Color) -> i32 {
+    let forward = get_forward_step(color);
+    let index = board_index as i32;
+    let is_isolated = [index % 10 - 1, index % 10 + 1].iter()
+        .all(|file| !has_pawn_on_file(board, *file, color));
+    let is_doubled = get_squares_ahead(index, forward).any(|ahead| is_pawn(board, ahead, color));
+    let is_in_chain = is_pawn(board, index - forward - 1, color) || is_pawn(board, index - forward + 1, color);
+    let is_connected = is_pawn(board, index - 1, color) || is_pawn(board, index + 1, color);
+    let rank = get_relative_rank(board_index, color);
+    let mut value = 0;
+
+    if is_isolated {
+        value -= ISOLATED_PAWN_PENALTY;
+    } else if !is_in_chain && !is_connected && is_backward(board, index, color) {
+        value -= BACKWARD_PAWN_PENALTY;
+    }
+    if is_doubled {
+        value -= DOUBLED_PAWN_PENALTY;
+    }
+    if is_in_chain {
+        value += PAWN_CHAIN_BONUS[rank];
+    }
+    if is_connected {
+        value += CONNECTED_PAWN_BONUS[rank];
+    }
+    value
+}
+
+//No enemy pawn ahead of it on its own or a neighbouring file
+fn is_passed_pawn(board: &Board, board_index: usize, color: Color) -> bool {
+    let forward = get_forward_step(color);
+    let enemy_color = get_enemy_color(color);
+    [-1, 0, 1].iter()
+        .flat_map(|side_step| get_squares_ahead(board_index as i32 + side_step, forward))
+        .all(|ahead| !is_pawn(board, ahead, enemy_color))
+}
+
+//Every pawn of its own on a neighbouring file is further ahead, so none can come up to protect it,
+//and an enemy pawn guards the square in front of it
+fn is_backward(board: &Board, index: i32, color: Color) -> bool {
+    let forward = get_forward_step(color);
+    let enemy_color = get_enemy_color(color);
+    let stop_square = index + forward;
+    let has_support_behind = [-1, 1].iter()
+        .flat_map(|side_step| get_squares_ahead(index + side_step, -forward).chain(Some(index + side_step)))
+        .any(|behind| is_pawn(board, behind, color));
+    let is_stop_square_guarded = is_pawn(board, stop_square + forward - 1, enemy_color)
+        || is_pawn(board, stop_square + forward + 1, enemy_color);
+    !has_support_behind && is_stop_square_guarded
+}
+
+fn get_squares_ahead(index: i32, forward: i32) -> impl Iterator<Item = i32> {
+    (1..8).map(move |steps| index + forward * steps)
+        .take_while(|ahead| (11..=88).contains(ahead))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use board::Move;
+    use board::helpers;
+
+    mod evaluate_pawn_structure {
+        use super::*;
+
+        #[test]
+        fn it_is_balanced_in_the_starting_position() {
+            let board_string = String::from("00000000000rnbqkbnr00pppppppp00--------00--------00--------00--------00PPPPPPPP00RNBQKBNR00000000000");
+            let board: Board = Board::new(board_string, Color::White);
+            assert_eq!(evaluate_pawn_structure(&board, MAX_GAME_PHASE), 0);
+        }
+
+        #[test]
+        fn it_rewards_a_protected_passed_pawn_over_a_blocked_one() {
+            let board_string = String::from("00000000000----k---00--------00---P----00----P---00--------00--------00--------00----K---00000000000");
+            let protected_board: Board = Board::new(board_string, Color::White);
+            let board_string = String::from("00000000000----k---00---n----00---P----00--------00--------00--------00----P---00----K---00000000000");
+            let blocked_board: Board = Board::new(board_string, Color::White);
+
+            assert!(evaluate_pawn_structure(&protected_board, 0) > evaluate_pawn_structure(&blocked_board, 0));
+        }
+
+        #[test]
+        fn it_values_passed_pawns_more_in_the_endgame() {
+            let board_string = String::from("00000000000----k---00--------00---P----00--------00--------00--------00--------00----K---00000000000");
+            let board: Board = Board::new(board_string, Color::White);
+            assert!(evaluate_pawn_structure(&board, 0) > evaluate_pawn_structure(&board, MAX_GAME_PHASE));
+        }
+
+        #[test]
+        fn it_penalizes_isolated_and_doubled_pawns() {
+            let board_string = String::from("00000000000----k---00ppp-----00--------00--------00--------00--P-----00--P---P-00----K---00000000000");
+            let board: Board = Board::new(board_string, Color::White);
+            //White: three isolated pawns, one of them doubled, and a passed g2 pawn.
+            //Black: three connected pawns and a passed a7 pawn
+            let white_value = -3 * ISOLATED_PAWN_PENALTY - DOUBLED_PAWN_PENALTY + PASSED_PAWN_MIDGAME_BONUS[2];
+            let black_value = 3 * CONNECTED_PAWN_BONUS[2] + PASSED_PAWN_MIDGAME_BONUS[2];
+            assert_eq!(evaluate_pawn_structure(&board, MAX_GAME_PHASE), white_value - black_value);
+        }
+
+        #[test]
+        fn it_penalizes_a_backward_pawn() {
+            let board_string = String::from("00000000000----k---00--------00--------00----p---00--P-----00---P----00--------00----K---00000000000");
+            let board: Board = Board::new(board_string, Color::White);
+            let board_string = String::from("00000000000----k---00--------00--------00--------00--P-----00---P----00--------00----K---00000000000");
+            let unguarded_board: Board = Board::new(board_string, Color::White);
+            let d3 = helpers::square_to_index(String::from("d3"));
+
+            assert_eq!(get_pawn_value(&board, d3, Color::White), -BACKWARD_PAWN_PENALTY);
+            assert_eq!(get_pawn_value(&unguarded_board, d3, Color::White), 0);
+        }
+
+        #[test]
+        fn it_rewards_a_pawn_chain_more_the_further_it_has_come() {
+            let board_string = String::from("00000000000----k---00--------00--------00--------00--------00--P-----00-P------00----K---00000000000");
+            let low_chain_board: Board = Board::new(board_string, Color::White);
+            let board_string = String::from("00000000000----k---00--------00--P-----00-P------00--------00--------00--------00----K---00000000000");
+            let high_chain_board: Board = Board::new(board_string, Color::White);
+            let c3 = helpers::square_to_index(String::from("c3"));
+            let c6 = helpers::square_to_index(String::from("c6"));
+
+            assert_eq!(get_pawn_value(&low_chain_board, c3, Color::White), PAWN_CHAIN_BONUS[3]);
+            assert!(get_pawn_value(&high_chain_board, c6, Color::White) > get_pawn_value(&low_chain_board, c3, Color::White));
+        }
+    }
+
+    mod pawn_hash_table {
+        use super::*;
+
+        #[test]
+        fn it_matches_the_uncached_evaluation() {
+            let board_string = String::from("00000000000----k---00--------00---P----00--------00--------00--------00--------00----K---00000000000");
+            let mut board: Board = Board::new(board_string, Color::White);
+            let mut pawn_table = PawnHashTable::new(1024);
+            let uncached_value = evaluate_pawn_structure(&board, 10);
+
+            assert_eq!(pawn_table.evaluate(&board, 10), uncached_value);
+            assert_eq!(pawn_table.evaluate(&board, 10), uncached_value);
+            //The cached passed pawn is blocked now
+            board.make_move(Move::from_chess_move((String::from("e8"), String::from("d7"))));
+            assert_eq!(pawn_table.evaluate(&board, 10), evaluate_pawn_structure(&board, 10));
+            assert!(pawn_table.evaluate(&board, 10) < uncached_value);
+        }
+    }
+}
@@ -0,0 +1,241 @@
+use board::Board;
+use board::Piece;
+use board::PieceType;
+use board::square::Color;
+use board::square::{ get_enemy_color, get_forward_step, get_relative_rank, has_pawn_on_file, is_pawn };
+use board::square::valid_moves;
+use super::MAX_GAME_PHASE;
+
+//Mobility is scored per move above or below what a piece typically has, so that the terms add up
+//to roughly nothing in the starting position's middlegame
+const KNIGHT_TYPICAL_MOBILITY: i32 = 4;
+const BISHOP_TYPICAL_MOBILITY: i32 = 7;
+const ROOK_TYPICAL_MOBILITY: i32 = 7;
+const QUEEN_TYPICAL_MOBILITY: i32 = 14;
+const KNIGHT_MOBILITY_WEIGHT: (i32, i32) = (4, 4);
+const BISHOP_MOBILITY_WEIGHT: (i32, i32) = (5, 5);
+const ROOK_MOBILITY_WEIGHT: (i32, i32) = (2, 4);
+const QUEEN_MOBILITY_WEIGHT: (i32, i32) = (1, 2);
+
+//Midgame and endgame bonuses
+const ROOK_ON_OPEN_FILE_BONUS: (i32, i32) = (20, 10);
+const ROOK_ON_SEMI_OPEN_FILE_BONUS: (i32, i32) = (10, 5);
+const ROOK_ON_SEVENTH_RANK_BONUS: (i32, i32) = (20, 30);
+const KNIGHT_OUTPOST_BONUS: (i32, i32) = (20, 10);
+const BISHOP_PAIR_BONUS: (i32, i32) = (30, 50);
+
+//From white's perspective
+pub fn evaluate_piece_activity(board: &Board, phase: u32) -> i32 {
+    let (white_mobility, white_placement) = evaluate_piece_activity_of_color(board, Color::White, phase);
+    let (black_mobility, black_placement) = evaluate_piece_activity_of_color(board, Color::Black, phase);
+    white_mobility + white_placement - black_mobility - black_placement
+}
+
+//Mobility, then everything else about where the pieces stand, both from the color's own perspective
+pub fn evaluate_piece_activity_of_color(board: &Board, color: Color, phase: u32) -> (i32, i32) {
+    let mut mobility = (0, 0);
+    let mut placement = (0, 0);
+    let mut bishop_count = 0;
+
+    for (board_index, square) in board.squares.iter().enumerate() {
+        let piece = match square.piece {
+            Some(p) if p.color == color => p,
+            _ => continue,
+        };
+        if piece.piece_type == PieceType::Bishop {
+            bishop_count += 1;
+        }
+        let (piece_mobility, piece_placement) = get_piece_activity(board, board_index, piece);
+        mobility = add(mobility, piece_mobility);
+        placement = add(placement, piece_placement);
+    }
+    if bishop_count >= 2 {
+        placement = add(placement, BISHOP_PAIR_BONUS);
+    }
+    (taper(mobility, phase), taper(placement, phase))
+}
+
+//Midgame and endgame mobility and placement bonuses of a single piece
+fn get_piece_activity(board: &Board, board_index: usize, piece: Piece) -> ((i32, i32), (i32, i32)) {
+    let index = board_index as i8;
+    //Minor pieces and queens that have not moved yet are left to the piece-square tables, otherwise
+    //every pawn move that opens a line for them would look like development
+    let is_undeveloped = is_on_starting_square(board_index, piece);
+    match piece.piece_type {
+        PieceType::Knight => {
+            let mobility = valid_moves::get_knight_moves(index, board, piece.color).len() as i32;
+            let placement = match is_outpost(board, board_index, piece.color) {
+                true => KNIGHT_OUTPOST_BONUS,
+                false => (0, 0),
+            };
+            match is_undeveloped {
+                true => ((0, 0), placement),
+                false => (get_mobility_bonus(mobility, KNIGHT_TYPICAL_MOBILITY, KNIGHT_MOBILITY_WEIGHT), placement),
+            }
+        },
+        PieceType::Bishop if is_undeveloped => ((0, 0), (0, 0)),
+        PieceType::Bishop => {
+            let mobility = valid_moves::get_bishop_moves(index, board, piece.color).len() as i32;
+            (get_mobility_bonus(mobility, BISHOP_TYPICAL_MOBILITY, BISHOP_MOBILITY_WEIGHT), (0, 0))
+        },
+        PieceType::Rook => {
+            let mobility = valid_moves::get_rook_moves(index, board, piece.color).len() as i32;
+            let mut placement = (0, 0);
+            let file = (board_index % 10) as i32;
+            if !has_pawn_on_file(board, file, piece.color) {
+                placement = match has_pawn_on_file(board, file, get_enemy_color(piece.color)) {
+                    true => ROOK_ON_SEMI_OPEN_FILE_BONUS,
+                    false => ROOK_ON_OPEN_FILE_BONUS,
+                };
+            }
+            if get_relative_rank(board_index, piece.color) == 7 {
+                placement = add(placement, ROOK_ON_SEVENTH_RANK_BONUS);
+            }
+            (get_mobility_bonus(mobility, ROOK_TYPICAL_MOBILITY, ROOK_MOBILITY_WEIGHT), placement)
+        },
+        PieceType::Queen if is_undeveloped => ((0, 0), (0, 0)),
+        PieceType::Queen => {
+            let mobility = valid_moves::get_queen_moves(index, board, piece.color).len() as i32;
+            (get_mobility_bonus(mobility, QUEEN_TYPICAL_MOBILITY, QUEEN_MOBILITY_WEIGHT), (0, 0))
+        },
+        PieceType::Pawn | PieceType::King => ((0, 0), (0, 0)),
+    }
+}
+
+fn is_on_starting_square(board_index: usize, piece: Piece) -> bool {
+    let starting_files: &[usize] = match piece.piece_type {
+        PieceType::Knight => &[2, 7],
+        PieceType::Bishop => &[3, 6],
+        PieceType::Queen => &[4],
+        _ => &[],
+    };
+    get_relative_rank(board_index, piece.color) == 1 && starting_files.contains(&(board_index % 10))
+}
+
+fn taper(value: (i32, i32), phase: u32) -> i32 {
+    (value.0 * phase as i32 + value.1 * (MAX_GAME_PHASE - phase) as i32) / MAX_GAME_PHASE as i32
+}
+
+fn get_mobility_bonus(mobility: i32, typical_mobility: i32, weight: (i32, i32)) -> (i32, i32) {
+    ((mobility - typical_mobility) * weight.0, (mobility - typical_mobility) * weight.1)
+}
+
+fn add(value: (i32, i32), bonus: (i32, i32)) -> (i32, i32) {
+    (value.0 + bonus.0, value.1 + bonus.1)
+}
+
+//A square in the enemy half, defended by a pawn of its own, that no enemy pawn can ever attack
+fn is_outpost(board: &Board, board_index: usize, color: Color) -> bool {
+    let rank = get_relative_rank(board_index, color);
+    if !(4..=6).contains(&rank) {
+        return false;
+    }
+    let index = board_index as i32;
+    let forward = get_forward_step(color);
+    let is_defended = is_pawn(board, index - forward - 1, color) || is_pawn(board, index - forward + 1, color);
+    let can_be_attacked = [-1, 1].iter()
+        .flat_map(|side_step| (1..8).map(move |steps| index + side_step + forward * steps))
+        .any(|ahead| is_pawn(board, ahead, get_enemy_color(color)));
+    is_defended && !can_be_attacked
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use board::helpers;
+
+    mod evaluate_piece_activity {
+        use super::*;
+
+        #[test]
+        fn it_is_balanced_in_the_starting_position() {
+            let board_string = String::from("00000000000rnbqkbnr00pppppppp00--------00--------00--------00--------00PPPPPPPP00RNBQKBNR00000000000");
+            let board: Board = Board::new(board_string, Color::White);
+            assert_eq!(evaluate_piece_activity(&board, MAX_GAME_PHASE), 0);
+        }
+
+        #[test]
+        fn it_prefers_an_active_bishop_to_a_buried_one() {
+            let board_string = String::from("00000000000----k---00--------00--------00--------00---B----00--------00--------00----K---00000000000");
+            let active_board: Board = Board::new(board_string, Color::White);
+            let board_string = String::from("00000000000----k---00--------00--------00--------00--------00--------00PPP-----00-B--K---00000000000");
+            let buried_board: Board = Board::new(board_string, Color::White);
+            let (active_bishop, _) = get_piece_activity(&active_board, helpers::square_to_index(String::from("d4")), Piece::new(PieceType::Bishop, Color::White));
+            let (buried_bishop, _) = get_piece_activity(&buried_board, helpers::square_to_index(String::from("b1")), Piece::new(PieceType::Bishop, Color::White));
+
+            assert!(active_bishop.0 > buried_bishop.0);
+            assert!(active_bishop.1 > buried_bishop.1);
+        }
+
+        #[test]
+        fn it_rewards_the_bishop_pair() {
+            let board_string = String::from("00000000000----k---00--------00--------00--------00--------00--------00--------00--B-KB--00000000000");
+            let pair_board: Board = Board::new(board_string, Color::White);
+            let board_string = String::from("00000000000----k---00--------00--------00--------00--------00--------00--------00--N-KB--00000000000");
+            let mixed_board: Board = Board::new(board_string, Color::White);
+            let (pair_mobility, pair_placement) = evaluate_piece_activity_of_color(&pair_board, Color::White, 0);
+            let (mixed_mobility, mixed_placement) = evaluate_piece_activity_of_color(&mixed_board, Color::White, 0);
+
+            assert_eq!(pair_placement - mixed_placement, BISHOP_PAIR_BONUS.1);
+            assert_eq!(evaluate_piece_activity(&pair_board, 0) - evaluate_piece_activity(&mixed_board, 0),
+                BISHOP_PAIR_BONUS.1 + pair_mobility - mixed_mobility);
+        }
+    }
+
+    mod get_piece_activity {
+        use super::*;
+
+        #[test]
+        fn it_rewards_rooks_on_open_and_semi_open_files() {
+            let board_string = String::from("00000000000----k---00p-------00--------00--------00--------00--------00-P------00R-R-K---00000000000");
+            let board: Board = Board::new(board_string, Color::White);
+            let rook = Piece::new(PieceType::Rook, Color::White);
+            let semi_open_rook = get_piece_activity(&board, helpers::square_to_index(String::from("a1")), rook);
+            let open_rook = get_piece_activity(&board, helpers::square_to_index(String::from("c1")), rook);
+            let semi_open_mobility = valid_moves::get_rook_moves(81, &board, Color::White).len() as i32;
+            let open_mobility = valid_moves::get_rook_moves(83, &board, Color::White).len() as i32;
+
+            assert_eq!(semi_open_rook, (get_mobility_bonus(semi_open_mobility, ROOK_TYPICAL_MOBILITY, ROOK_MOBILITY_WEIGHT), ROOK_ON_SEMI_OPEN_FILE_BONUS));
+            assert_eq!(open_rook, (get_mobility_bonus(open_mobility, ROOK_TYPICAL_MOBILITY, ROOK_MOBILITY_WEIGHT), ROOK_ON_OPEN_FILE_BONUS));
+        }
+
+        #[test]
+        fn it_rewards_a_rook_on_the_seventh_rank() {
+            let board_string = String::from("00000000000----k---00R-------00--------00--------00--------00--------00--------00----K---00000000000");
+            let board: Board = Board::new(board_string, Color::White);
+            let seventh_rank_rook = get_piece_activity(&board, helpers::square_to_index(String::from("a7")), Piece::new(PieceType::Rook, Color::White));
+            let mobility = valid_moves::get_rook_moves(21, &board, Color::White).len() as i32;
+            let expected = (get_mobility_bonus(mobility, ROOK_TYPICAL_MOBILITY, ROOK_MOBILITY_WEIGHT), add(ROOK_ON_OPEN_FILE_BONUS, ROOK_ON_SEVENTH_RANK_BONUS));
+
+            assert_eq!(seventh_rank_rook, expected);
+        }
+
+        #[test]
+        fn it_leaves_undeveloped_pieces_to_the_piece_square_tables() {
+            let board_string = String::from("00000000000----k---00--------00--------00--------00--------00--------00--------00-NBQK---00000000000");
+            let board: Board = Board::new(board_string, Color::White);
+            let knight = get_piece_activity(&board, helpers::square_to_index(String::from("b1")), Piece::new(PieceType::Knight, Color::White));
+            let bishop = get_piece_activity(&board, helpers::square_to_index(String::from("c1")), Piece::new(PieceType::Bishop, Color::White));
+            let queen = get_piece_activity(&board, helpers::square_to_index(String::from("d1")), Piece::new(PieceType::Queen, Color::White));
+            assert_eq!((knight.0, bishop.0, queen.0), ((0, 0), (0, 0), (0, 0)));
+
+            let board_string = String::from("00000000000----k---00--------00--------00--------00--------00--------00--------00-BNQK---00000000000");
+            let board: Board = Board::new(board_string, Color::White);
+            let bishop = get_piece_activity(&board, helpers::square_to_index(String::from("b1")), Piece::new(PieceType::Bishop, Color::White));
+            let mobility = valid_moves::get_bishop_moves(82, &board, Color::White).len() as i32;
+            assert_eq!(bishop.0, get_mobility_bonus(mobility, BISHOP_TYPICAL_MOBILITY, BISHOP_MOBILITY_WEIGHT));
+        }
+
+        #[test]
+        fn it_recognizes_knight_outposts() {
+            let board_string = String::from("00000000000----k---00--------00--------00---N----00--P-----00--------00--------00----K---00000000000");
+            let board: Board = Board::new(board_string, Color::White);
+            let d5 = helpers::square_to_index(String::from("d5"));
+            assert!(is_outpost(&board, d5, Color::White));
+
+            let board_string = String::from("00000000000----k---00----p---00--------00---N----00--P-----00--------00--------00----K---00000000000");
+            let board: Board = Board::new(board_string, Color::White);
+            assert!(!is_outpost(&board, d5, Color::White));
+        }
+    }
+}
@@ -0,0 +1,148 @@
+use board::Move;
+use board::Board;
+use board::PieceType;
+use board::helpers;
+use board::square::is_check;
+use super::get_all_legal_moves;
+
+pub fn move_to_san(board: &Board, chess_move: &Move) -> String {
+    let piece = match board.get_piece_at(chess_move.from) {
+        Some(p) => p,
+        None => panic!("There is no piece on the square from which the move is being made: {}", chess_move),
+    };
+    let is_capture = board.get_piece_at(chess_move.to).is_some();
+    let (from_square, to_square) = chess_move.to_chess_move();
+    let mut san = String::new();
+
+    match piece.piece_type {
+        PieceType::Pawn => {
+            if is_capture {
+                san.push_str(&from_square[0..1]);
+            }
+        },
+        _ => {
+            san.push(get_piece_letter(piece.piece_type));
+            san.push_str(&get_disambiguation(board, chess_move, piece.piece_type));
+        },
+    }
+
+    if is_capture {
+        san.push('x');
+    }
+    san.push_str(&to_square);
+
+    let next_board = board.test_move(*chess_move);
+    if is_check(next_board.clone()) {
+        match get_all_legal_moves(&next_board).is_empty() {
+            true => san.push('#'),
+            false => san.push('+'),
+        }
+    }
+
+    san
+}
+
+pub fn line_to_san(board: &Board, line: &[Move]) -> Vec<String> {
+    let mut current_board = board.clone();
+    line.iter()
+        .map(|chess_move| {
+            let san = move_to_san(&current_board, chess_move);
+            current_board.make_move(*chess_move);
+            san
+        })
+        .collect()
+}
+
+fn get_piece_letter(piece_type: PieceType) -> char {
+    match piece_type {
+        PieceType::Pawn => 'P',
+        PieceType::Knight => 'N',
+        PieceType::Bishop => 'B',
+        PieceType::Rook => 'R',
+        PieceType::Queen => 'Q',
+        PieceType::King => 'K',
+    }
+}
+
+fn get_disambiguation(board: &Board, chess_move: &Move, piece_type: PieceType) -> String {
+    let from_square = helpers::index_to_square(chess_move.from);
+    let rivals: Vec<String> = get_all_legal_moves(board).into_iter()
+        .filter(|legal_move| legal_move.to == chess_move.to && legal_move.from != chess_move.from)
+        .filter(|legal_move| match board.get_piece_at(legal_move.from) {
+            Some(p) => p.piece_type == piece_type,
+            None => false,
+        })
+        .map(|legal_move| helpers::index_to_square(legal_move.from))
+        .collect();
+
+    if rivals.is_empty() {
+        String::new()
+    } else if rivals.iter().all(|rival| rival[0..1] != from_square[0..1]) {
+        String::from(&from_square[0..1])
+    } else if rivals.iter().all(|rival| rival[1..2] != from_square[1..2]) {
+        String::from(&from_square[1..2])
+    } else {
+        from_square
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use board::square::Color;
+
+    mod move_to_san {
+        use super::*;
+
+        #[test]
+        fn it_writes_pawn_and_piece_moves() {
+            let board_string = String::from("00000000000rnbqkbnr00pppppppp00--------00--------00--------00--------00PPPPPPPP00RNBQKBNR00000000000");
+            let board: Board = Board::new(board_string, Color::White);
+            assert_eq!(move_to_san(&board, &Move::from_chess_move((String::from("e2"), String::from("e4")))), "e4");
+            assert_eq!(move_to_san(&board, &Move::from_chess_move((String::from("g1"), String::from("f3")))), "Nf3");
+        }
+
+        #[test]
+        fn it_writes_captures_and_checks() {
+            let board_string = String::from("00000000000rnbqkbnr00pppppppp00--------00--------00--------00--------00PPPPPPPP00RNBQKBNR00000000000");
+            let mut board: Board = Board::new(board_string, Color::White);
+            board.make_move(Move::from_chess_move((String::from("e2"), String::from("e4"))));
+            board.make_move(Move::from_chess_move((String::from("d7"), String::from("d5"))));
+            assert_eq!(move_to_san(&board, &Move::from_chess_move((String::from("e4"), String::from("d5")))), "exd5");
+            assert_eq!(move_to_san(&board, &Move::from_chess_move((String::from("f1"), String::from("b5")))), "Bb5+");
+        }
+
+        #[test]
+        fn it_writes_checkmate() {
+            let board_string = String::from("00000000000rnbqkbnr00pppppppp00--------00--------00--------00--------00PPPPPPPP00RNBQKBNR00000000000");
+            let mut board: Board = Board::new(board_string, Color::White);
+            board.make_move(Move::from_chess_move((String::from("f2"), String::from("f3"))));
+            board.make_move(Move::from_chess_move((String::from("e7"), String::from("e5"))));
+            board.make_move(Move::from_chess_move((String::from("g2"), String::from("g4"))));
+            assert_eq!(move_to_san(&board, &Move::from_chess_move((String::from("d8"), String::from("h4")))), "Qh4#");
+        }
+
+        #[test]
+        fn it_disambiguates_pieces_moving_to_the_same_square() {
+            let board_string = String::from("00000000000----k---00--------00--------00--------00--------00--------00--------00N---N--K00000000000");
+            let board: Board = Board::new(board_string, Color::White);
+            assert_eq!(move_to_san(&board, &Move::from_chess_move((String::from("a1"), String::from("c2")))), "Nac2");
+        }
+    }
+
+    mod line_to_san {
+        use super::*;
+
+        #[test]
+        fn it_converts_a_line_of_moves() {
+            let board_string = String::from("00000000000rnbqkbnr00pppppppp00--------00--------00--------00--------00PPPPPPPP00RNBQKBNR00000000000");
+            let board: Board = Board::new(board_string, Color::White);
+            let line = vec![
+                Move::from_chess_move((String::from("e2"), String::from("e4"))),
+                Move::from_chess_move((String::from("e7"), String::from("e5"))),
+                Move::from_chess_move((String::from("g1"), String::from("f3"))),
+            ];
+            assert_eq!(line_to_san(&board, &line), vec!["e4", "e5", "Nf3"]);
+        }
+    }
+}
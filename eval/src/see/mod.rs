@@ -0,0 +1,150 @@
+use std::cmp;
+use board::Move;
+use board::Board;
+use board::Piece;
+use board::PieceType;
+use board::square::Color;
+use board::square::get_enemy_color;
+use board::square::valid_moves::is_out_of_bounds;
+use super::{ PAWN_VALUE, KNIGHT_VALUE, BISHOP_VALUE, ROOK_VALUE, QUEEN_VALUE, KING_VALUE };
+
+const BISHOP_DIRECTIONS: [i8; 4] = [9, 11, -9, -11];
+const ROOK_DIRECTIONS: [i8; 4] = [-1, 1, -10, 10];
+const KNIGHT_STEPS: [i8; 8] = [-12, -21, -19, -8, 12, 21, 19, 8];
+const KING_STEPS: [i8; 8] = [-1, -11, -10, -9, 1, 11, 10, 9];
+
+//Material won or lost by the side making the move once every recapture on the target square
+//has been played out, each side always recapturing with its least valuable attacker and
+//free to stop whenever continuing would lose material
+pub fn see(board: &Board, chess_move: &Move) -> i32 {
+    let mut pieces: Vec<Option<Piece>> = board.squares.iter().map(|square| square.piece).collect();
+    let attacker = match pieces[chess_move.from] {
+        Some(p) => p,
+        None => panic!("There is no piece on the square from which the move is being made: {}", chess_move),
+    };
+
+    let mut gains: Vec<i32> = vec![pieces[chess_move.to].map_or(0, |victim| get_piece_value(victim.piece_type))];
+    let mut value_on_target = get_piece_value(attacker.piece_type);
+    pieces[chess_move.from] = None;
+    let mut color = get_enemy_color(attacker.color);
+
+    //Removing each attacker before looking for the next one uncovers the pieces behind it
+    while let Some((attacker_index, attacker_type)) = get_least_valuable_attacker(&pieces, chess_move.to, color) {
+        pieces[attacker_index] = None;
+        if attacker_type == PieceType::King && get_least_valuable_attacker(&pieces, chess_move.to, get_enemy_color(color)).is_some() {
+            break;
+        }
+        let previous_gain = *gains.last().unwrap();
+        gains.push(value_on_target - previous_gain);
+        value_on_target = get_piece_value(attacker_type);
+        color = get_enemy_color(color);
+    }
+
+    while gains.len() > 1 {
+        let gain = gains.pop().unwrap();
+        let previous_gain = gains.last_mut().unwrap();
+        *previous_gain = -cmp::max(-*previous_gain, gain);
+    }
+    gains[0]
+}
+
+fn get_least_valuable_attacker(pieces: &[Option<Piece>], target: usize, color: Color) -> Option<(usize, PieceType)> {
+    let target = target as i8;
+    let pawn_steps: [i8; 2] = match color {
+        Color::White => [9, 11],
+        Color::Black => [-9, -11],
+    };
+    let is_attacker = |index: i8, piece_types: &[PieceType]| match pieces[index as usize] {
+        Some(p) => p.color == color && piece_types.contains(&p.piece_type),
+        None => false,
+    };
+    let find_stepping_attacker = |steps: &[i8], piece_types: &[PieceType]| steps.iter()
+        .map(|step| target + step)
+        .find(|from| !is_out_of_bounds(*from) && is_attacker(*from, piece_types));
+    let find_sliding_attacker = |directions: &[i8], piece_types: &[PieceType]| directions.iter()
+        .filter_map(|direction| {
+            let mut from = target + direction;
+            while !is_out_of_bounds(from) && pieces[from as usize].is_none() {
+                from += direction;
+            }
+            match !is_out_of_bounds(from) && is_attacker(from, piece_types) {
+                true => Some(from),
+                false => None,
+            }
+        })
+        .min_by_key(|from| get_piece_value(pieces[*from as usize].unwrap().piece_type));
+
+    let attacker_index = find_stepping_attacker(&pawn_steps, &[PieceType::Pawn])
+        .or_else(|| find_stepping_attacker(&KNIGHT_STEPS, &[PieceType::Knight]))
+        .or_else(|| find_sliding_attacker(&BISHOP_DIRECTIONS, &[PieceType::Bishop]))
+        .or_else(|| find_sliding_attacker(&ROOK_DIRECTIONS, &[PieceType::Rook]))
+        .or_else(|| find_sliding_attacker(&BISHOP_DIRECTIONS, &[PieceType::Queen]))
+        .or_else(|| find_sliding_attacker(&ROOK_DIRECTIONS, &[PieceType::Queen]))
+        .or_else(|| find_stepping_attacker(&KING_STEPS, &[PieceType::King]))?;
+
+    Some((attacker_index as usize, pieces[attacker_index as usize].unwrap().piece_type))
+}
+
+fn get_piece_value(piece_type: PieceType) -> i32 {
+    let value = match piece_type {
+        PieceType::Pawn => PAWN_VALUE,
+        PieceType::Knight => KNIGHT_VALUE,
+        PieceType::Bishop => BISHOP_VALUE,
+        PieceType::Rook => ROOK_VALUE,
+        PieceType::Queen => QUEEN_VALUE,
+        PieceType::King => KING_VALUE,
+    };
+    value as i32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    mod see {
+        use super::*;
+
+        #[test]
+        fn it_wins_an_undefended_piece() {
+            let board_string = String::from("00000000000----k---00--------00--------00--------00------n-00--------00--------00---QK---00000000000");
+            let board: Board = Board::new(board_string, Color::White);
+            assert_eq!(see(&board, &Move::from_chess_move((String::from("d1"), String::from("g4")))), 300);
+        }
+
+        #[test]
+        fn it_loses_the_queen_for_a_defended_piece() {
+            let board_string = String::from("00000000000----k---00--------00--------00-------p00------n-00--------00--------00---QK---00000000000");
+            let board: Board = Board::new(board_string, Color::White);
+            assert_eq!(see(&board, &Move::from_chess_move((String::from("d1"), String::from("g4")))), 300 - 900);
+        }
+
+        #[test]
+        fn it_counts_attackers_hidden_behind_other_attackers() {
+            let board_string = String::from("00000000000---rk---00---r----00--------00---p----00--------00--------00---R----00---RK---00000000000");
+            let board: Board = Board::new(board_string, Color::White);
+            assert_eq!(see(&board, &Move::from_chess_move((String::from("d2"), String::from("d5")))), 100 - 500);
+
+            let board_string = String::from("00000000000----k---00---r----00--------00---p----00--------00--------00---R----00---RK---00000000000");
+            let board: Board = Board::new(board_string, Color::White);
+            assert_eq!(see(&board, &Move::from_chess_move((String::from("d2"), String::from("d5")))), 100);
+        }
+
+        #[test]
+        fn it_stops_recapturing_when_it_would_lose_material() {
+            let board_string = String::from("00000000000----k---00--------00----p---00---p----00----P---00--------00--------00---QK---00000000000");
+            let board: Board = Board::new(board_string, Color::White);
+            assert_eq!(see(&board, &Move::from_chess_move((String::from("e4"), String::from("d5")))), 100);
+        }
+
+        #[test]
+        fn it_does_not_let_the_king_recapture_a_defended_piece() {
+            let board_string = String::from("00000000000----k---00--------00--------00--------00-----b--00--q-----00---N----00----K---00000000000");
+            let board: Board = Board::new(board_string, Color::Black);
+            assert_eq!(see(&board, &Move::from_chess_move((String::from("c3"), String::from("d2")))), 300);
+
+            let board_string = String::from("00000000000----k---00--------00--------00--------00--------00--q-----00---N----00----K---00000000000");
+            let board: Board = Board::new(board_string, Color::Black);
+            assert_eq!(see(&board, &Move::from_chess_move((String::from("c3"), String::from("d2")))), 300 - 900);
+        }
+    }
+}
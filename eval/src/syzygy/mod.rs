@@ -0,0 +1,1160 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::io::Read;
+use std::path::{ Path, PathBuf };
+use std::sync::OnceLock;
+use board::Board;
+use board::PieceType;
+use board::square::Color;
+use board::square::is_in_check;
+use super::get_all_legal_moves;
+use super::tablebase::{ Tablebase, Wdl, count_pieces, get_syzygy_pieces };
+
+//First bytes of Syzygy WDL and DTZ files
+const SYZYGY_WDL_MAGIC: [u8; 4] = [0x71, 0xE8, 0x23, 0x5D];
+const SYZYGY_DTZ_MAGIC: [u8; 4] = [0xD7, 0x66, 0x0C, 0xA5];
+const SYZYGY_WDL_EXTENSION: &str = "rtbw";
+const SYZYGY_DTZ_EXTENSION: &str = "rtbz";
+const MAX_TABLE_PIECES: usize = 7;
+
+//Flags of the file header
+const HEADER_HAS_PAWNS: u8 = 2;
+//Flags of each table in a file. All but the last one are only used by DTZ tables
+const FLAG_SIDE_TO_MOVE: u8 = 1;
+const FLAG_MAPPED: u8 = 2;
+const FLAG_WIN_PLIES: u8 = 4;
+const FLAG_LOSS_PLIES: u8 = 8;
+const FLAG_WIDE: u8 = 16;
+const FLAG_SINGLE_VALUE: u8 = 128;
+//Right child of a symbol that stands for a single value rather than a pair of symbols
+const LEAF_SYMBOL: u16 = 0xFFF;
+//Positions of the four results in the value maps of DTZ tables, by result from loss to win
+const DTZ_MAP_POSITIONS: [usize; 5] = [1, 3, 0, 2, 0];
+const SPARSE_ENTRY_SIZE: usize = 6;
+
+//Squares below are numbered from 0 for a1 to 63 for h8, as in the files
+const fn get_rank(square: usize) -> usize {
+    square / 8
+}
+
+const fn get_file(square: usize) -> usize {
+    square % 8
+}
+
+//Positive above the a1-h8 diagonal and negative below it
+const fn get_diagonal_offset(square: usize) -> i32 {
+    get_rank(square) as i32 - get_file(square) as i32
+}
+
+const fn is_king_distance(first: usize, second: usize) -> bool {
+    let file_distance = get_file(first) as i32 - get_file(second) as i32;
+    let rank_distance = get_rank(first) as i32 - get_rank(second) as i32;
+    file_distance >= -1 && file_distance <= 1 && rank_distance >= -1 && rank_distance <= 1
+}
+
+//Ways to choose k of n squares, by k and n
+const BINOMIAL: [[u64; 64]; MAX_TABLE_PIECES] = get_binomial();
+//The 28 squares below the a1-h8 diagonal, in order
+const MAP_B1H1H7: [u64; 64] = get_map_b1h1h7();
+//The a1-d1-d4 triangle, with the squares on the diagonal last
+const MAP_A1D1D4: [u64; 64] = get_map_a1d1d4();
+//The 462 placements of two kings that symmetry allows, by the triangle square of the first
+const MAP_KK: [[u64; 64]; 10] = get_map_kk();
+//Pawn squares from 47 to 0, so that the leading pawn, nearest the edge and lowest, has the largest value
+const MAP_PAWNS: [usize; 64] = get_map_pawns();
+const LEAD_PAWN_INDICES: [[u64; 64]; MAX_TABLE_PIECES] = get_lead_pawn_indices().0;
+const LEAD_PAWN_TABLE_SIZES: [[u64; 4]; MAX_TABLE_PIECES] = get_lead_pawn_indices().1;
+
+const fn get_binomial() -> [[u64; 64]; MAX_TABLE_PIECES] {
+    let mut binomial = [[0; 64]; MAX_TABLE_PIECES];
+    let mut n = 0;
+    while n < 64 {
+        let mut k = 0;
+        while k < MAX_TABLE_PIECES && k <= n {
+            binomial[k][n] = match k == 0 || k == n {
+                true => 1,
+                false => binomial[k - 1][n - 1] + binomial[k][n - 1],
+            };
+            k += 1;
+        }
+        n += 1;
+    }
+    binomial
+}
+
+const fn get_map_b1h1h7() -> [u64; 64] {
+    let mut map = [0; 64];
+    let mut code = 0;
+    let mut square = 0;
+    while square < 64 {
+        if get_diagonal_offset(square) < 0 {
+            map[square] = code;
+            code += 1;
+        }
+        square += 1;
+    }
+    map
+}
+
+const fn get_map_a1d1d4() -> [u64; 64] {
+    let mut map = [0; 64];
+    let mut code = 0;
+    let mut square = 0;
+    while square < 64 {
+        if get_diagonal_offset(square) < 0 && get_file(square) <= 3 && get_rank(square) <= 3 {
+            map[square] = code;
+            code += 1;
+        }
+        square += 1;
+    }
+    square = 0;
+    while square < 64 {
+        if get_diagonal_offset(square) == 0 && get_file(square) <= 3 {
+            map[square] = code;
+            code += 1;
+        }
+        square += 1;
+    }
+    map
+}
+
+//With the first king on the diagonal the second is kept below it, and placements with both kings on the
+//diagonal come last
+const fn get_map_kk() -> [[u64; 64]; 10] {
+    let mut map = [[0; 64]; 10];
+    let mut both_on_diagonal = [(0, 0); 64];
+    let mut both_on_diagonal_count = 0;
+    let mut code = 0;
+    let mut triangle_index = 0;
+    while triangle_index < 10 {
+        let mut first = 0;
+        while first < 64 {
+            //Squares outside the triangle are also mapped to 0, which belongs to b1
+            let is_in_triangle = get_file(first) <= 3 && get_rank(first) <= 3 && get_diagonal_offset(first) <= 0;
+            if is_in_triangle && MAP_A1D1D4[first] == triangle_index as u64 && (triangle_index != 0 || first == 1) {
+                let mut second = 0;
+                while second < 64 {
+                    if is_king_distance(first, second) || (get_diagonal_offset(first) == 0 && get_diagonal_offset(second) > 0) {
+                    } else if get_diagonal_offset(first) == 0 && get_diagonal_offset(second) == 0 {
+                        both_on_diagonal[both_on_diagonal_count] = (triangle_index, second);
+                        both_on_diagonal_count += 1;
+                    } else {
+                        map[triangle_index][second] = code;
+                        code += 1;
+                    }
+                    second += 1;
+                }
+            }
+            first += 1;
+        }
+        triangle_index += 1;
+    }
+    let mut i = 0;
+    while i < both_on_diagonal_count {
+        let (triangle_index, second) = both_on_diagonal[i];
+        map[triangle_index][second] = code;
+        code += 1;
+        i += 1;
+    }
+    map
+}
+
+const fn get_map_pawns() -> [usize; 64] {
+    let mut map = [0; 64];
+    let mut available = 48;
+    let mut file = 0;
+    while file < 4 {
+        let mut rank = 1;
+        while rank < 7 {
+            let square = rank * 8 + file;
+            map[square] = available - 1;
+            map[square ^ 7] = available - 2;
+            available -= 2;
+            rank += 1;
+        }
+        file += 1;
+    }
+    map
+}
+
+//Where the placements of a number of leading pawns start for each square of the leading one, and how many
+//there are for each of its files, as files a to d each have their own table
+const fn get_lead_pawn_indices() -> ([[u64; 64]; MAX_TABLE_PIECES], [[u64; 4]; MAX_TABLE_PIECES]) {
+    let mut indices = [[0; 64]; MAX_TABLE_PIECES];
+    let mut table_sizes = [[0; 4]; MAX_TABLE_PIECES];
+    let mut lead_pawn_count = 1;
+    while lead_pawn_count < MAX_TABLE_PIECES {
+        let mut file = 0;
+        while file < 4 {
+            let mut index = 0;
+            let mut rank = 1;
+            while rank < 7 {
+                let square = rank * 8 + file;
+                indices[lead_pawn_count][square] = index;
+                index += BINOMIAL[lead_pawn_count - 1][MAP_PAWNS[square]];
+                rank += 1;
+            }
+            table_sizes[lead_pawn_count][file] = index;
+            file += 1;
+        }
+        lead_pawn_count += 1;
+    }
+    (indices, table_sizes)
+}
+
+//Piece codes of the files: 1 to 6 for white pawn to king, and 9 to 14 for black
+fn get_piece_code(piece_type: PieceType, color: Color) -> u8 {
+    let code = piece_type as u8 + 1;
+    match color {
+        Color::White => code,
+        Color::Black => code + 8,
+    }
+}
+
+fn get_board_index(square: usize) -> usize {
+    (8 - get_rank(square)) * 10 + get_file(square) + 1
+}
+
+fn get_wdl(value: i32) -> Wdl {
+    match value {
+        -2 => Wdl::Loss,
+        -1 => Wdl::BlessedLoss,
+        0 => Wdl::Draw,
+        1 => Wdl::CursedWin,
+        _ => Wdl::Win,
+    }
+}
+
+//Captures and pawn moves are not stored in DTZ tables, but the result right before them is known
+fn get_dtz_before_zeroing(wdl: i32) -> i32 {
+    match wdl {
+        2 => 1,
+        1 => 101,
+        -1 => -101,
+        -2 => -1,
+        _ => 0,
+    }
+}
+
+fn is_zeroing_move(board: &Board, chess_move: board::Move) -> bool {
+    board.get_piece_at(chess_move.to).is_some()
+        || board.get_piece_at(chess_move.from).map(|p| p.piece_type) == Some(PieceType::Pawn)
+}
+
+//The material of a table, with the side first in its name as white
+struct Material {
+    piece_count: usize,
+    has_pawns: bool,
+    //Whether some piece other than a king is alone of its kind, which lets three pieces lead the encoding
+    has_unique_pieces: bool,
+    //Of the side whose pawns lead the encoding, which is the one with fewer pawns, and then of the other side
+    pawn_counts: [usize; 2],
+    is_symmetric: bool,
+}
+
+impl Material {
+    fn from_name(name: &str) -> Option<Material> {
+        let mut sides = name.split('v');
+        let (white_pieces, black_pieces) = match (sides.next(), sides.next(), sides.next()) {
+            (Some(white_pieces), Some(black_pieces), None) => (white_pieces, black_pieces),
+            _ => return None,
+        };
+        let mut counts = [[0; 6]; 2];
+        for (color, pieces) in [white_pieces, black_pieces].iter().enumerate() {
+            for letter in pieces.chars() {
+                let piece_type = match letter {
+                    'P' => PieceType::Pawn,
+                    'N' => PieceType::Knight,
+                    'B' => PieceType::Bishop,
+                    'R' => PieceType::Rook,
+                    'Q' => PieceType::Queen,
+                    'K' => PieceType::King,
+                    _ => return None,
+                };
+                counts[color][piece_type as usize] += 1;
+            }
+        }
+        let piece_count = name.len() - 1;
+        if counts[0][PieceType::King as usize] != 1 || counts[1][PieceType::King as usize] != 1 || piece_count > MAX_TABLE_PIECES {
+            return None;
+        }
+        let (white_pawns, black_pawns) = (counts[0][PieceType::Pawn as usize], counts[1][PieceType::Pawn as usize]);
+        let is_white_leading = black_pawns == 0 || (white_pawns > 0 && black_pawns >= white_pawns);
+        Some(Material {
+            piece_count,
+            has_pawns: white_pawns + black_pawns > 0,
+            has_unique_pieces: counts.iter().any(|color_counts| color_counts[..PieceType::King as usize].contains(&1)),
+            pawn_counts: match is_white_leading {
+                true => [white_pawns, black_pawns],
+                false => [black_pawns, white_pawns],
+            },
+            is_symmetric: white_pieces == black_pieces,
+        })
+    }
+}
+
+//Everything needed to decode the values of one side to move and, in tables with pawns, one file of the
+//leading pawn. Offsets point into the file
+#[derive(Clone, Default)]
+struct PairsData {
+    flags: u8,
+    //The value itself in tables that store a single one
+    min_symbol_length: u32,
+    block_size: usize,
+    span: u64,
+    block_count: usize,
+    block_length_count: usize,
+    sparse_index_count: usize,
+    lowest_symbols: Vec<u16>,
+    //The lowest code of each symbol length, padded to 64 bits
+    base: Vec<u64>,
+    //The two symbols each symbol stands for, or its value as the first one for a leaf
+    pairs: Vec<(u16, u16)>,
+    //How many values, minus one, each symbol stands for
+    symbol_lengths: Vec<u32>,
+    sparse_index: usize,
+    block_lengths: usize,
+    data: usize,
+    //Pieces in the order they are encoded, where pieces in a row of the same kind form a group
+    pieces: [u8; MAX_TABLE_PIECES],
+    group_lengths: [usize; MAX_TABLE_PIECES + 1],
+    group_indices: [u64; MAX_TABLE_PIECES + 1],
+    //Where the values of each result start in the value map of DTZ tables
+    dtz_map_starts: [usize; 4],
+}
+
+enum TableValue {
+    Found(i32),
+    //DTZ tables only store one side to move
+    OtherSideToMove,
+}
+
+//A WDL or DTZ file, read into memory the first time a position is probed in it
+struct Table {
+    bytes: Vec<u8>,
+    //By side to move, and by file of the leading pawn
+    pairs_data: Vec<Vec<PairsData>>,
+}
+
+impl Table {
+    fn parse(bytes: Vec<u8>, material: &Material, is_wdl: bool) -> Option<Table> {
+        let magic = match is_wdl {
+            true => SYZYGY_WDL_MAGIC,
+            false => SYZYGY_DTZ_MAGIC,
+        };
+        if bytes.get(..4)? != magic || (bytes.get(4)? & HEADER_HAS_PAWNS != 0) != material.has_pawns {
+            return None;
+        }
+        let side_count = match is_wdl && !material.is_symmetric {
+            true => 2,
+            false => 1,
+        };
+        let file_count = match material.has_pawns {
+            true => 4,
+            false => 1,
+        };
+        let has_pawns_on_both_sides = material.has_pawns && material.pawn_counts[1] > 0;
+        let mut pairs_data = vec![vec![PairsData::default(); file_count]; side_count];
+        let mut offset = 5;
+        for file in 0..file_count {
+            let order_bytes = [*bytes.get(offset)?, match has_pawns_on_both_sides {
+                true => *bytes.get(offset + 1)?,
+                false => 0xFF,
+            }];
+            offset += 1 + has_pawns_on_both_sides as usize;
+            for (side, side_data) in pairs_data.iter_mut().enumerate() {
+                let data = &mut side_data[file];
+                for (k, piece) in data.pieces.iter_mut().take(material.piece_count).enumerate() {
+                    let byte = *bytes.get(offset + k)?;
+                    *piece = match side {
+                        0 => byte & 0xF,
+                        _ => byte >> 4,
+                    };
+                }
+                let order = match side {
+                    0 => [order_bytes[0] & 0xF, order_bytes[1] & 0xF],
+                    _ => [order_bytes[0] >> 4, order_bytes[1] >> 4],
+                };
+                set_groups(data, material, order, file);
+            }
+            offset += material.piece_count;
+        }
+        offset += offset & 1;
+
+        for file in 0..file_count {
+            for side_data in pairs_data.iter_mut() {
+                offset = set_sizes(&bytes, &mut side_data[file], offset)?;
+            }
+        }
+        if !is_wdl {
+            offset = set_dtz_map(&bytes, &mut pairs_data[0], offset)?;
+        }
+        for file in 0..file_count {
+            for side_data in pairs_data.iter_mut() {
+                side_data[file].sparse_index = offset;
+                offset += side_data[file].sparse_index_count * SPARSE_ENTRY_SIZE;
+            }
+        }
+        for file in 0..file_count {
+            for side_data in pairs_data.iter_mut() {
+                side_data[file].block_lengths = offset;
+                offset += side_data[file].block_length_count * 2;
+            }
+        }
+        for file in 0..file_count {
+            for side_data in pairs_data.iter_mut() {
+                offset = (offset + 0x3F) & !0x3F;
+                side_data[file].data = offset;
+                offset += side_data[file].block_count * side_data[file].block_size;
+            }
+        }
+        Some(Table { bytes, pairs_data })
+    }
+
+    //Bytes past the end of the file read as zeros, as the last block may be cut short
+    fn read_big_endian(&self, offset: usize, length: usize) -> u64 {
+        (0..length).fold(0, |value, i| (value << 8) | *self.bytes.get(offset + i).unwrap_or(&0) as u64)
+    }
+
+    fn read_u16(&self, offset: usize) -> Option<u16> {
+        read_u16(&self.bytes, offset)
+    }
+
+    fn get_block_length(&self, data: &PairsData, block: usize) -> Option<i64> {
+        match block < data.block_length_count {
+            true => Some(self.read_u16(data.block_lengths + block * 2)? as i64),
+            false => None,
+        }
+    }
+
+    //Values are compressed by replacing frequent pairs of symbols with new symbols, which are then Huffman
+    //coded in blocks. A sparse index points into the blocks every span values
+    fn decompress(&self, data: &PairsData, index: u64) -> Option<u32> {
+        if data.flags & FLAG_SINGLE_VALUE != 0 {
+            return Some(data.min_symbol_length);
+        }
+        let sparse_entry = data.sparse_index + (index / data.span) as usize * SPARSE_ENTRY_SIZE;
+        let mut block = read_u32(&self.bytes, sparse_entry)? as usize;
+        let mut offset = self.read_u16(sparse_entry + 4)? as i64 + (index % data.span) as i64 - (data.span / 2) as i64;
+        while offset < 0 {
+            block = block.checked_sub(1)?;
+            offset += self.get_block_length(data, block)? + 1;
+        }
+        while offset > self.get_block_length(data, block)? {
+            offset -= self.get_block_length(data, block)? + 1;
+            block += 1;
+        }
+
+        let mut position = data.data + block * data.block_size;
+        let mut buffer = self.read_big_endian(position, 8);
+        let mut buffer_size: u32 = 64;
+        position += 8;
+        let mut symbol;
+        loop {
+            let mut length = 0;
+            while buffer < data.base[length] {
+                length += 1;
+            }
+            let shift = 64 - length as u32 - data.min_symbol_length;
+            symbol = ((buffer - data.base[length]).checked_shr(shift).unwrap_or(0) + data.lowest_symbols[length] as u64) as usize;
+            let symbol_length = *data.symbol_lengths.get(symbol)? as i64;
+            if offset <= symbol_length {
+                break;
+            }
+            offset -= symbol_length + 1;
+            let code_length = length as u32 + data.min_symbol_length;
+            buffer = buffer.checked_shl(code_length).unwrap_or(0);
+            buffer_size = buffer_size.checked_sub(code_length)?;
+            if buffer_size <= 32 {
+                buffer_size += 32;
+                buffer |= self.read_big_endian(position, 4) << (64 - buffer_size);
+                position += 4;
+            }
+        }
+
+        //Pairs are expanded until the symbol standing for the value alone
+        while data.symbol_lengths[symbol] != 0 {
+            let (left, right) = data.pairs[symbol];
+            let left_length = data.symbol_lengths[left as usize] as i64;
+            if offset <= left_length {
+                symbol = left as usize;
+            } else {
+                offset -= left_length + 1;
+                symbol = right as usize;
+            }
+        }
+        Some(data.pairs[symbol].0 as u32)
+    }
+}
+
+fn read_u16(bytes: &[u8], offset: usize) -> Option<u16> {
+    Some(u16::from_le_bytes([*bytes.get(offset)?, *bytes.get(offset + 1)?]))
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> Option<u32> {
+    Some(u32::from_le_bytes([*bytes.get(offset)?, *bytes.get(offset + 1)?, *bytes.get(offset + 2)?, *bytes.get(offset + 3)?]))
+}
+
+//Each group is encoded as one number, and the order bytes of the file tell which group varies slowest.
+//Without pawns the leading group is the three unique pieces or else both kings, and with pawns it is the
+//leading pawns, followed by the other side's pawns
+fn set_groups(data: &mut PairsData, material: &Material, order: [u8; 2], file: usize) {
+    let mut first_length: i32 = match (material.has_pawns, material.has_unique_pieces) {
+        (true, _) => 0,
+        (false, true) => 3,
+        (false, false) => 2,
+    };
+    let mut group_count = 0;
+    data.group_lengths[0] = 1;
+    for i in 1..material.piece_count {
+        first_length -= 1;
+        if first_length > 0 || data.pieces[i] == data.pieces[i - 1] {
+            data.group_lengths[group_count] += 1;
+        } else {
+            group_count += 1;
+            data.group_lengths[group_count] = 1;
+        }
+    }
+    group_count += 1;
+    data.group_lengths[group_count] = 0;
+
+    let has_pawns_on_both_sides = material.has_pawns && material.pawn_counts[1] > 0;
+    let mut next = 1 + has_pawns_on_both_sides as usize;
+    let mut free_squares = 64 - data.group_lengths[0] - match has_pawns_on_both_sides {
+        true => data.group_lengths[1],
+        false => 0,
+    };
+    let mut index = 1;
+    let mut k = 0;
+    while next < group_count || k == order[0] as usize || k == order[1] as usize {
+        if k == order[0] as usize {
+            data.group_indices[0] = index;
+            index *= match (material.has_pawns, material.has_unique_pieces) {
+                (true, _) => LEAD_PAWN_TABLE_SIZES[data.group_lengths[0]][file],
+                (false, true) => 31332,
+                (false, false) => 462,
+            };
+        } else if k == order[1] as usize {
+            data.group_indices[1] = index;
+            index *= BINOMIAL[data.group_lengths[1]][48 - data.group_lengths[0]];
+        } else {
+            data.group_indices[next] = index;
+            index *= BINOMIAL[data.group_lengths[next]][free_squares];
+            free_squares -= data.group_lengths[next];
+            next += 1;
+        }
+        k += 1;
+    }
+    data.group_indices[group_count] = index;
+}
+
+//Reads the block sizes and the symbols of the canonical Huffman code
+fn set_sizes(bytes: &[u8], data: &mut PairsData, mut offset: usize) -> Option<usize> {
+    data.flags = *bytes.get(offset)?;
+    if data.flags & FLAG_SINGLE_VALUE != 0 {
+        data.min_symbol_length = *bytes.get(offset + 1)? as u32;
+        return Some(offset + 2);
+    }
+    let group_count = data.group_lengths.iter().position(|length| *length == 0)?;
+    let table_size = data.group_indices[group_count];
+    data.block_size = 1usize.checked_shl(*bytes.get(offset + 1)? as u32)?;
+    data.span = 1u64.checked_shl(*bytes.get(offset + 2)? as u32)?;
+    data.sparse_index_count = table_size.div_ceil(data.span) as usize;
+    let padding = *bytes.get(offset + 3)? as usize;
+    data.block_count = read_u32(bytes, offset + 4)? as usize;
+    //Padded so that the sparse index never points past the block lengths
+    data.block_length_count = data.block_count + padding;
+    let max_symbol_length = *bytes.get(offset + 8)? as u32;
+    data.min_symbol_length = *bytes.get(offset + 9)? as u32;
+    if max_symbol_length < data.min_symbol_length || max_symbol_length > 64 {
+        return None;
+    }
+    offset += 10;
+
+    let length_count = (max_symbol_length - data.min_symbol_length + 1) as usize;
+    data.lowest_symbols = (0..length_count).map(|i| read_u16(bytes, offset + i * 2)).collect::<Option<_>>()?;
+    offset += length_count * 2;
+    //Longer codes have lower values, so the lowest code of each length is found from the next longer one
+    data.base = vec![0; length_count];
+    for i in (0..length_count - 1).rev() {
+        data.base[i] = data.base[i + 1].wrapping_add(data.lowest_symbols[i] as u64).wrapping_sub(data.lowest_symbols[i + 1] as u64) / 2;
+    }
+    for (i, base) in data.base.iter_mut().enumerate() {
+        *base = base.checked_shl(64 - i as u32 - data.min_symbol_length).unwrap_or(0);
+    }
+
+    let symbol_count = read_u16(bytes, offset)? as usize;
+    offset += 2;
+    data.pairs = (0..symbol_count)
+        .map(|symbol| {
+            let pair = bytes.get(offset + symbol * 3..offset + symbol * 3 + 3)?;
+            Some((((pair[1] as u16 & 0xF) << 8) | pair[0] as u16, ((pair[2] as u16) << 4) | (pair[1] as u16 >> 4)))
+        })
+        .collect::<Option<_>>()?;
+    if data.pairs.iter().any(|(left, right)| *right != LEAF_SYMBOL && (*left as usize >= symbol_count || *right as usize >= symbol_count)) {
+        return None;
+    }
+    data.symbol_lengths = vec![0; symbol_count];
+    let mut is_visited = vec![false; symbol_count];
+    for symbol in 0..symbol_count {
+        set_symbol_length(data, symbol, &mut is_visited);
+    }
+    Some(offset + symbol_count * 3 + (symbol_count & 1))
+}
+
+fn set_symbol_length(data: &mut PairsData, symbol: usize, is_visited: &mut [bool]) {
+    if is_visited[symbol] {
+        return;
+    }
+    is_visited[symbol] = true;
+    let (left, right) = data.pairs[symbol];
+    if right == LEAF_SYMBOL {
+        return;
+    }
+    set_symbol_length(data, left as usize, is_visited);
+    set_symbol_length(data, right as usize, is_visited);
+    data.symbol_lengths[symbol] = data.symbol_lengths[left as usize].saturating_add(data.symbol_lengths[right as usize]).saturating_add(1);
+}
+
+//DTZ values are stored by how often they occur, with maps back to the real values for each result
+fn set_dtz_map(bytes: &[u8], file_data: &mut [PairsData], mut offset: usize) -> Option<usize> {
+    for data in file_data.iter_mut() {
+        if data.flags & FLAG_MAPPED == 0 {
+            continue;
+        }
+        let is_wide = data.flags & FLAG_WIDE != 0;
+        if is_wide {
+            offset += offset & 1;
+        }
+        for start in data.dtz_map_starts.iter_mut() {
+            match is_wide {
+                true => {
+                    *start = offset + 2;
+                    offset += 2 * read_u16(bytes, offset)? as usize + 2;
+                },
+                false => {
+                    *start = offset + 1;
+                    offset += *bytes.get(offset)? as usize + 1;
+                },
+            }
+        }
+    }
+    Some(offset + (offset & 1))
+}
+
+struct SyzygyTable {
+    material: Material,
+    wdl_path: PathBuf,
+    dtz_path: Option<PathBuf>,
+    wdl: OnceLock<Option<Table>>,
+    dtz: OnceLock<Option<Table>>,
+}
+
+//The Syzygy tables found in a directory. Files are only read once a position is probed in them
+pub struct SyzygyTables {
+    //By name, such as KRvK, for the tables with a WDL file
+    tables: HashMap<String, SyzygyTable>,
+}
+
+impl SyzygyTables {
+    pub fn scan(directory: &Path) -> io::Result<SyzygyTables> {
+        let mut wdl_paths = HashMap::new();
+        let mut dtz_paths = HashMap::new();
+        for entry in fs::read_dir(directory)? {
+            let path = entry?.path();
+            let (name, extension) = match (path.file_stem().and_then(|name| name.to_str()), path.extension().and_then(|extension| extension.to_str())) {
+                (Some(name), Some(extension)) => (String::from(name), extension),
+                _ => continue,
+            };
+            let (magic, paths) = match extension {
+                SYZYGY_WDL_EXTENSION => (SYZYGY_WDL_MAGIC, &mut wdl_paths),
+                SYZYGY_DTZ_EXTENSION => (SYZYGY_DTZ_MAGIC, &mut dtz_paths),
+                _ => continue,
+            };
+            let mut header = [0; 4];
+            fs::File::open(&path)?.read_exact(&mut header)?;
+            if header != magic {
+                return Err(io::Error::new(io::ErrorKind::InvalidData, format!("{} is not a Syzygy table", path.display())));
+            }
+            paths.insert(name, path);
+        }
+        let tables = wdl_paths.into_iter()
+            .filter_map(|(name, wdl_path)| {
+                let material = Material::from_name(&name)?;
+                let dtz_path = dtz_paths.remove(&name);
+                Some((name, SyzygyTable { material, wdl_path, dtz_path, wdl: OnceLock::new(), dtz: OnceLock::new() }))
+            })
+            .collect();
+        Ok(SyzygyTables { tables })
+    }
+
+    pub fn table_count(&self) -> usize {
+        self.tables.len()
+    }
+
+    //A table serves both colors, so KRvK also covers positions where black has the rook
+    pub fn has_table(&self, board: &Board) -> bool {
+        self.find_table(board).is_some()
+    }
+
+    //The table of the material on the board, and whether colors have to be swapped to look the position up
+    fn find_table(&self, board: &Board) -> Option<(&SyzygyTable, bool)> {
+        let white_pieces = get_syzygy_pieces(board, Color::White);
+        let black_pieces = get_syzygy_pieces(board, Color::Black);
+        //Symmetric tables only store positions with white to move
+        let is_black_symmetric = white_pieces == black_pieces && board.current_turn.color == Color::Black;
+        match self.tables.get(&format!("{}v{}", white_pieces, black_pieces)) {
+            Some(table) => Some((table, is_black_symmetric)),
+            None => self.tables.get(&format!("{}v{}", black_pieces, white_pieces)).map(|table| (table, true)),
+        }
+    }
+
+    fn probe_table(&self, board: &Board, is_wdl: bool, wdl: i32) -> Option<TableValue> {
+        if count_pieces(board) == 2 {
+            return Some(TableValue::Found(0));
+        }
+        let (table, is_flipped) = self.find_table(board)?;
+        let loaded_table = match is_wdl {
+            true => table.wdl.get_or_init(|| fs::read(&table.wdl_path).ok().and_then(|bytes| Table::parse(bytes, &table.material, true))),
+            false => table.dtz.get_or_init(|| {
+                let bytes = fs::read(table.dtz_path.as_ref()?).ok()?;
+                Table::parse(bytes, &table.material, false)
+            }),
+        };
+        let loaded_table = loaded_table.as_ref()?;
+        let material = &table.material;
+
+        //Tables are stored with the side first in their name as white, so otherwise colors are swapped and
+        //the board is flipped
+        let (flip_color, flip_squares) = match is_flipped {
+            true => (8, 56),
+            false => (0, 0),
+        };
+        let side = is_flipped as usize ^ (board.current_turn.color == Color::Black) as usize;
+        let mut squares = Vec::with_capacity(MAX_TABLE_PIECES);
+        let mut pieces = Vec::with_capacity(MAX_TABLE_PIECES);
+        let board_pieces: Vec<(usize, u8)> = (0..64)
+            .filter_map(|square| {
+                let piece = board.get_piece_at(get_board_index(square))?;
+                Some((square, get_piece_code(piece.piece_type, piece.color)))
+            })
+            .collect();
+        //Pawns cannot stand on the first or last rank in the tables
+        if board_pieces.iter().any(|(square, code)| code & 7 == 1 && (get_rank(*square) == 0 || get_rank(*square) == 7)) {
+            return None;
+        }
+
+        //With pawns there is a table for each file of the leading pawn, whose color the tables also give
+        let mut file = 0;
+        let mut lead_pawn_code = None;
+        if material.has_pawns {
+            let code = loaded_table.pairs_data[0][0].pieces[0] ^ flip_color;
+            for (square, _) in board_pieces.iter().filter(|(_, piece_code)| *piece_code == code) {
+                squares.push(square ^ flip_squares);
+                pieces.push(code ^ flip_color);
+            }
+            let lead_pawn = (0..squares.len()).fold(0, |lead, i| match MAP_PAWNS[squares[i]] > MAP_PAWNS[squares[lead]] {
+                true => i,
+                false => lead,
+            });
+            squares.swap(0, lead_pawn);
+            file = get_file(squares[0]).min(7 - get_file(squares[0]));
+            lead_pawn_code = Some(code);
+        }
+        let lead_pawn_count = squares.len();
+
+        if !is_wdl {
+            let flags = loaded_table.pairs_data[0][file].flags;
+            if (flags & FLAG_SIDE_TO_MOVE) as usize != side && (!material.is_symmetric || material.has_pawns) {
+                return Some(TableValue::OtherSideToMove);
+            }
+        }
+        for (square, code) in board_pieces.iter().filter(|(_, code)| Some(*code) != lead_pawn_code) {
+            squares.push(square ^ flip_squares);
+            pieces.push(code ^ flip_color);
+        }
+        if squares.len() != material.piece_count {
+            return None;
+        }
+
+        let side_data = &loaded_table.pairs_data[side % loaded_table.pairs_data.len()];
+        let data = &side_data[file];
+        //Pieces are put in the order of the table
+        for i in lead_pawn_count..squares.len() - 1 {
+            if let Some(j) = (i + 1..squares.len()).find(|j| pieces[*j] == data.pieces[i]) {
+                pieces.swap(i, j);
+                squares.swap(i, j);
+            }
+        }
+        let index = get_index(&mut squares, material, data, lead_pawn_count);
+        let value = loaded_table.decompress(data, index)? as i32;
+        match is_wdl {
+            true => Some(TableValue::Found(value - 2)),
+            false => Some(TableValue::Found(get_dtz_value(loaded_table, &side_data[file], value, wdl)?)),
+        }
+    }
+
+    //The result of the side to move, and whether a capture or pawn move keeps it. Values stored for positions
+    //with a winning capture, or a drawing one when the position is lost, are whatever compressed best, so
+    //captures are searched as well. For DTZ, pawn moves are too
+    fn search(&self, board: &Board, is_dtz: bool) -> Option<(i32, bool)> {
+        let moves = get_all_legal_moves(board);
+        let mut best_value = -2;
+        let mut move_count = 0;
+        for chess_move in moves.iter() {
+            let is_searched = match is_dtz {
+                true => is_zeroing_move(board, *chess_move),
+                false => board.get_piece_at(chess_move.to).is_some(),
+            };
+            if !is_searched {
+                continue;
+            }
+            move_count += 1;
+            let value = -self.search(&board.test_move(*chess_move), false)?.0;
+            if value > best_value {
+                best_value = value;
+                if value == 2 {
+                    return Some((value, true));
+                }
+            }
+        }
+        let has_no_more_moves = move_count > 0 && move_count == moves.len();
+        let value = match has_no_more_moves {
+            true => best_value,
+            false => match self.probe_table(board, true, 0)? {
+                TableValue::Found(value) => value,
+                TableValue::OtherSideToMove => return None,
+            },
+        };
+        match best_value >= value {
+            true => Some((best_value, best_value > 0 || has_no_more_moves)),
+            false => Some((value, false)),
+        }
+    }
+
+    fn get_dtz(&self, board: &Board) -> Option<i32> {
+        let (wdl, is_zeroing_best) = self.search(board, true)?;
+        if wdl == 0 {
+            return Some(0);
+        }
+        if is_zeroing_best {
+            return Some(get_dtz_before_zeroing(wdl));
+        }
+        match self.probe_table(board, false, wdl)? {
+            TableValue::Found(dtz) => {
+                let fifty_move_plies = match wdl.abs() == 1 {
+                    true => 100,
+                    false => 0,
+                };
+                Some((dtz + fifty_move_plies) * wdl.signum())
+            },
+            //One ply deeper the table has the right side to move
+            TableValue::OtherSideToMove => {
+                let mut best_dtz = None;
+                for chess_move in get_all_legal_moves(board) {
+                    let is_zeroing = is_zeroing_move(board, chess_move);
+                    let next_board = board.test_move(chess_move);
+                    let mut dtz = match is_zeroing {
+                        true => -get_dtz_before_zeroing(self.search(&next_board, false)?.0),
+                        false => -self.get_dtz(&next_board)?,
+                    };
+                    if dtz == 1 && is_in_check(&next_board, next_board.current_turn.color) && get_all_legal_moves(&next_board).is_empty() {
+                        return Some(1);
+                    }
+                    if !is_zeroing {
+                        dtz += dtz.signum();
+                    }
+                    if dtz.signum() == wdl.signum() && best_dtz.is_none_or(|best_dtz| dtz < best_dtz) {
+                        best_dtz = Some(dtz);
+                    }
+                }
+                Some(best_dtz.unwrap_or(-1))
+            },
+        }
+    }
+}
+
+impl Tablebase for SyzygyTables {
+    fn max_pieces(&self) -> u32 {
+        self.tables.values().map(|table| table.material.piece_count as u32).max().unwrap_or(0)
+    }
+
+    fn probe_wdl(&self, board: &Board) -> Option<Wdl> {
+        self.search(board, false).map(|(wdl, _)| get_wdl(wdl))
+    }
+
+    fn probe_dtz(&self, board: &Board) -> Option<i32> {
+        self.get_dtz(board)
+    }
+}
+
+//The squares are mirrored so that the leading piece is in the a1-d1-d4 triangle, or the leading pawn on files
+//a to d, and each group is then numbered by the squares it takes
+fn get_index(squares: &mut [usize], material: &Material, data: &PairsData, lead_pawn_count: usize) -> u64 {
+    if get_file(squares[0]) > 3 {
+        for square in squares.iter_mut() {
+            *square ^= 7;
+        }
+    }
+    let mut index = match material.has_pawns {
+        true => {
+            let mut index = LEAD_PAWN_INDICES[lead_pawn_count][squares[0]];
+            squares[1..lead_pawn_count].sort_by_key(|square| MAP_PAWNS[*square]);
+            for (i, square) in squares.iter().enumerate().take(lead_pawn_count).skip(1) {
+                index += BINOMIAL[i][MAP_PAWNS[*square]];
+            }
+            index
+        },
+        false => get_piece_index(squares, material, data),
+    };
+
+    index *= data.group_indices[0];
+    let mut group_start = data.group_lengths[0];
+    let mut has_remaining_pawns = material.has_pawns && material.pawn_counts[1] > 0;
+    let mut next = 1;
+    while data.group_lengths[next] != 0 {
+        let group_end = group_start + data.group_lengths[next];
+        squares[group_start..group_end].sort_unstable();
+        let mut group_index = 0;
+        for i in group_start..group_end {
+            //Squares are numbered past the ones taken by earlier groups, and the other side's pawns past the first rank
+            let taken_below = squares[..group_start].iter().filter(|square| **square < squares[i]).count();
+            let pawn_rank_offset = match has_remaining_pawns {
+                true => 8,
+                false => 0,
+            };
+            group_index += BINOMIAL[i - group_start + 1][squares[i] - taken_below - pawn_rank_offset];
+        }
+        has_remaining_pawns = false;
+        index += group_index * data.group_indices[next];
+        group_start = group_end;
+        next += 1;
+    }
+    index
+}
+
+fn get_piece_index(squares: &mut [usize], material: &Material, data: &PairsData) -> u64 {
+    if get_rank(squares[0]) > 3 {
+        for square in squares.iter_mut() {
+            *square ^= 56;
+        }
+    }
+    //The first piece of the leading group off the a1-h8 diagonal is brought below it
+    if let Some(i) = (0..data.group_lengths[0]).find(|i| get_diagonal_offset(squares[*i]) != 0) {
+        if get_diagonal_offset(squares[i]) > 0 {
+            for square in squares[i..].iter_mut() {
+                *square = ((*square >> 3) | (*square << 3)) & 63;
+            }
+        }
+    }
+    if !material.has_unique_pieces {
+        return MAP_KK[MAP_A1D1D4[squares[0]] as usize][squares[1]];
+    }
+
+    //Three unique pieces: the first below the diagonal or on it, then the others with squares taken skipped
+    let adjust_1 = (squares[1] > squares[0]) as u64;
+    let adjust_2 = (squares[2] > squares[0]) as u64 + (squares[2] > squares[1]) as u64;
+    let (first, second, third) = (squares[0] as u64, squares[1] as u64, squares[2] as u64);
+    if get_diagonal_offset(squares[0]) != 0 {
+        (MAP_A1D1D4[squares[0]] * 63 + second - adjust_1) * 62 + third - adjust_2
+    } else if get_diagonal_offset(squares[1]) != 0 {
+        (6 * 63 + (first / 8) * 28 + MAP_B1H1H7[squares[1]]) * 62 + third - adjust_2
+    } else if get_diagonal_offset(squares[2]) != 0 {
+        6 * 63 * 62 + 4 * 28 * 62 + (first / 8) * 7 * 28 + (second / 8 - adjust_1) * 28 + MAP_B1H1H7[squares[2]]
+    } else {
+        6 * 63 * 62 + 4 * 28 * 62 + 4 * 7 * 28 + (first / 8) * 7 * 6 + (second / 8 - adjust_1) * 6 + third / 8 - adjust_2
+    }
+}
+
+//Stored values count moves rather than plies unless the flags say otherwise, and results decided by the
+//fifty-move rule always do
+fn get_dtz_value(table: &Table, data: &PairsData, value: i32, wdl: i32) -> Option<i32> {
+    let mut value = value;
+    if data.flags & FLAG_MAPPED != 0 {
+        let start = data.dtz_map_starts[DTZ_MAP_POSITIONS[(wdl + 2) as usize]];
+        value = match data.flags & FLAG_WIDE != 0 {
+            true => table.read_u16(start + 2 * value as usize)? as i32,
+            false => *table.bytes.get(start + value as usize)? as i32,
+        };
+    }
+    let is_in_plies = match wdl {
+        2 => data.flags & FLAG_WIN_PLIES != 0,
+        -2 => data.flags & FLAG_LOSS_PLIES != 0,
+        _ => false,
+    };
+    if !is_in_plies {
+        value *= 2;
+    }
+    Some(value + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    //Placements of king, rook and king that symmetry allows
+    const ROOK_ENDGAME_TABLE_SIZE: usize = 31332;
+    const VALUES_PER_BLOCK: usize = 256;
+    const SPAN: usize = 1024;
+    //Worked out by hand for white Kc1, Rh8 and black Ke3
+    const WINNING_INDEX: usize = 7769;
+
+    fn get_test_directory(name: &str) -> PathBuf {
+        let directory = std::env::temp_dir().join(format!("syzygy_{}_{}", name, std::process::id()));
+        fs::create_dir_all(&directory).unwrap();
+        directory
+    }
+
+    //A KRvK WDL file where each value takes one bit, standing for the first or second of the values given for
+    //each side to move. Only the position of the winning index has the second value
+    fn get_rook_endgame_wdl_file(values: [[u8; 2]; 2]) -> Vec<u8> {
+        let block_count = ROOK_ENDGAME_TABLE_SIZE.div_ceil(VALUES_PER_BLOCK);
+        let mut bytes = SYZYGY_WDL_MAGIC.to_vec();
+        //Both sides to move, the leading group first, pieces in the order K, R, k and a byte to align
+        bytes.extend_from_slice(&[1, 0, 0x66, 0x44, 0xEE, 0]);
+        for side_values in values.iter() {
+            bytes.extend_from_slice(&[0, 5, 10, 0]);
+            bytes.extend_from_slice(&(block_count as u32).to_le_bytes());
+            bytes.extend_from_slice(&[1, 1, 0, 0, 2, 0]);
+            for value in side_values.iter() {
+                bytes.extend_from_slice(&[*value, 0xF0, 0xFF]);
+            }
+        }
+        for _ in 0..2 {
+            for k in 0..ROOK_ENDGAME_TABLE_SIZE.div_ceil(SPAN) {
+                let index = k * SPAN + SPAN / 2;
+                bytes.extend_from_slice(&((index / VALUES_PER_BLOCK) as u32).to_le_bytes());
+                bytes.extend_from_slice(&((index % VALUES_PER_BLOCK) as u16).to_le_bytes());
+            }
+        }
+        for _ in 0..2 {
+            for block in 0..block_count {
+                let length = VALUES_PER_BLOCK.min(ROOK_ENDGAME_TABLE_SIZE - block * VALUES_PER_BLOCK) - 1;
+                bytes.extend_from_slice(&(length as u16).to_le_bytes());
+            }
+        }
+        for _ in 0..2 {
+            bytes.resize(bytes.len().next_multiple_of(64), 0);
+            let data = bytes.len();
+            bytes.resize(data + block_count * VALUES_PER_BLOCK / 8, 0);
+            bytes[data + WINNING_INDEX / 8] |= 0x80 >> (WINNING_INDEX % 8);
+        }
+        bytes
+    }
+
+    fn get_rook_endgame_tables(name: &str, dtz_file: Option<&[u8]>) -> SyzygyTables {
+        let directory = get_test_directory(name);
+        fs::write(directory.join("KRvK.rtbw"), get_rook_endgame_wdl_file([[2, 4], [0, 0]])).unwrap();
+        if let Some(dtz_file) = dtz_file {
+            fs::write(directory.join("KRvK.rtbz"), dtz_file).unwrap();
+        }
+        let tables = SyzygyTables::scan(&directory).unwrap();
+        //Files are only read when first probed, so both are probed before the directory goes
+        tables.probe_dtz(&Board::from_fen("7R/8/8/8/8/4k3/8/2K5 w - - 0 1").unwrap());
+        fs::remove_dir_all(&directory).unwrap();
+        tables
+    }
+
+    fn probe_wdl(tables: &SyzygyTables, fen: &str) -> Option<Wdl> {
+        tables.probe_wdl(&Board::from_fen(fen).unwrap())
+    }
+
+    mod indices {
+        use super::*;
+
+        #[test]
+        fn it_counts_the_placements_of_the_leading_pieces() {
+            assert_eq!(MAP_KK.iter().flatten().max(), Some(&461));
+            assert_eq!(MAP_A1D1D4[27], 9);
+            assert_eq!(MAP_B1H1H7[55], 27);
+            assert_eq!((MAP_PAWNS[8], MAP_PAWNS[15], MAP_PAWNS[9], MAP_PAWNS[12], MAP_PAWNS[52]), (47, 46, 35, 10, 0));
+            assert_eq!(LEAD_PAWN_TABLE_SIZES[1], [6; 4]);
+            assert_eq!(LEAD_PAWN_TABLE_SIZES[2][0], 6 * 47 - 30);
+        }
+    }
+
+    mod probe_wdl {
+        use super::*;
+
+        #[test]
+        fn it_decodes_the_value_of_a_position() {
+            let tables = get_rook_endgame_tables("wdl", None);
+
+            assert_eq!(probe_wdl(&tables, "7R/8/8/8/8/4k3/8/2K5 w - - 0 1"), Some(Wdl::Win));
+            assert_eq!(probe_wdl(&tables, "7R/8/8/8/4k3/8/8/2K5 w - - 0 1"), Some(Wdl::Draw));
+            assert_eq!(probe_wdl(&tables, "7R/8/8/8/8/4k3/8/2K5 b - - 0 1"), Some(Wdl::Loss));
+        }
+
+        #[test]
+        fn it_finds_mirrored_positions_and_swapped_colors() {
+            let tables = get_rook_endgame_tables("symmetry", None);
+
+            assert_eq!(probe_wdl(&tables, "R7/8/8/8/8/3k4/8/5K2 w - - 0 1"), Some(Wdl::Win));
+            assert_eq!(probe_wdl(&tables, "2K5/8/4k3/8/8/8/8/7R w - - 0 1"), Some(Wdl::Win));
+            assert_eq!(probe_wdl(&tables, "2k5/8/4K3/8/8/8/8/7r b - - 0 1"), Some(Wdl::Win));
+            assert_eq!(probe_wdl(&tables, "2k5/8/4K3/8/8/8/8/7r w - - 0 1"), Some(Wdl::Loss));
+        }
+
+        #[test]
+        fn it_searches_captures_the_table_does_not_store() {
+            let tables = get_rook_endgame_tables("captures", None);
+            assert_eq!(probe_wdl(&tables, "8/8/8/8/8/8/4kR2/K7 b - - 0 1"), Some(Wdl::Draw));
+        }
+
+        #[test]
+        fn it_needs_a_table_for_the_material() {
+            let tables = get_rook_endgame_tables("missing", None);
+
+            assert_eq!(probe_wdl(&tables, "8/8/8/8/8/8/4k3/K7 w - - 0 1"), Some(Wdl::Draw));
+            assert_eq!(probe_wdl(&tables, "7Q/8/8/8/8/4k3/8/2K5 w - - 0 1"), None);
+            assert_eq!(probe_wdl(&tables, "7R/8/8/8/8/4k3/n7/2K5 w - - 0 1"), None);
+        }
+    }
+
+    mod probe_dtz {
+        use super::*;
+
+        #[test]
+        fn it_converts_moves_to_plies() {
+            //White to move, and every position five moves from zeroing
+            let mut dtz_file = SYZYGY_DTZ_MAGIC.to_vec();
+            dtz_file.extend_from_slice(&[1, 0, 0x06, 0x04, 0x0E, 0, FLAG_SINGLE_VALUE, 5]);
+            let tables = get_rook_endgame_tables("dtz", Some(&dtz_file));
+
+            assert_eq!(tables.probe_dtz(&Board::from_fen("7R/8/8/8/8/4k3/8/2K5 w - - 0 1").unwrap()), Some(11));
+            assert_eq!(tables.probe_dtz(&Board::from_fen("7R/8/8/8/4k3/8/8/2K5 w - - 0 1").unwrap()), Some(0));
+        }
+
+        #[test]
+        fn it_needs_a_dtz_file() {
+            let tables = get_rook_endgame_tables("no_dtz", None);
+            assert_eq!(tables.probe_dtz(&Board::from_fen("7R/8/8/8/8/4k3/8/2K5 w - - 0 1").unwrap()), None);
+        }
+    }
+
+    mod scan {
+        use super::*;
+
+        #[test]
+        fn it_finds_valid_tables_in_a_directory() {
+            let directory = get_test_directory("scan");
+            fs::write(directory.join("KRvK.rtbw"), SYZYGY_WDL_MAGIC).unwrap();
+            fs::write(directory.join("KRvK.rtbz"), SYZYGY_DTZ_MAGIC).unwrap();
+            fs::write(directory.join("KQvKR.rtbw"), SYZYGY_WDL_MAGIC).unwrap();
+            fs::write(directory.join("README"), "not a table").unwrap();
+            let tables = SyzygyTables::scan(&directory).unwrap();
+            fs::write(directory.join("KBvK.rtbw"), SYZYGY_DTZ_MAGIC).unwrap();
+            let invalid_scan = SyzygyTables::scan(&directory);
+            fs::remove_dir_all(&directory).unwrap();
+
+            assert_eq!(tables.table_count(), 2);
+            assert_eq!(tables.max_pieces(), 4);
+            assert!(tables.has_table(&Board::from_fen("8/8/8/4k3/8/8/8/R3K3 b - - 0 1").unwrap()));
+            assert!(!tables.has_table(&Board::from_fen("8/8/8/4k3/8/8/8/B3K3 w - - 0 1").unwrap()));
+            //A file with only a header cannot be decoded
+            assert_eq!(tables.probe_wdl(&Board::from_fen("8/8/8/4k3/8/8/8/R3K3 b - - 0 1").unwrap()), None);
+            assert!(invalid_scan.is_err());
+        }
+    }
+}
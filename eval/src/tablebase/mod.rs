@@ -0,0 +1,189 @@
+use std::cmp;
+use board::Board;
+use board::Move;
+use board::PieceType;
+use board::square::Color;
+use board::square::{ get_enemy_color, is_in_check };
+use super::get_all_legal_moves;
+
+//Piece letters in the order Syzygy file names list them
+const SYZYGY_PIECE_ORDER: [(PieceType, char); 6] = [(PieceType::King, 'K'), (PieceType::Queen, 'Q'), (PieceType::Rook, 'R'), (PieceType::Bishop, 'B'), (PieceType::Knight, 'N'), (PieceType::Pawn, 'P')];
+
+//From the side to move's perspective. Cursed wins and blessed losses are only decided by the fifty-move rule
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Wdl {
+    Loss,
+    BlessedLoss,
+    Draw,
+    CursedWin,
+    Win,
+}
+
+impl Wdl {
+    pub fn for_opponent(self) -> Wdl {
+        match self {
+            Wdl::Loss => Wdl::Win,
+            Wdl::BlessedLoss => Wdl::CursedWin,
+            Wdl::Draw => Wdl::Draw,
+            Wdl::CursedWin => Wdl::BlessedLoss,
+            Wdl::Win => Wdl::Loss,
+        }
+    }
+}
+
+//Probes assume the fifty-move counter was just reset, as the board does not keep one
+pub trait Tablebase: Send + Sync {
+    //Most pieces, kings included, that a position may have for the tablebase to know it
+    fn max_pieces(&self) -> u32;
+    fn probe_wdl(&self, board: &Board) -> Option<Wdl>;
+    //Plies until the next capture or pawn move that keeps the result, negative when the side to move loses
+    fn probe_dtz(&self, board: &Board) -> Option<i32>;
+}
+
+pub fn count_pieces(board: &Board) -> u32 {
+    board.squares.iter().filter(|square| square.piece.is_some()).count() as u32
+}
+
+//The move keeping the best result, winning as fast and losing as slowly as distance to zeroing allows.
+//None when the position or any position after a move is missing from the tablebase
+pub fn probe_root(tablebase: &dyn Tablebase, board: &Board) -> Option<(Move, Wdl)> {
+    if count_pieces(board) > tablebase.max_pieces() {
+        return None;
+    }
+    let mut best: Option<(Move, Wdl, i32)> = None;
+    for chess_move in get_all_legal_moves(board) {
+        let next_board = board.test_move(chess_move);
+        let (wdl, distance) = match get_all_legal_moves(&next_board).is_empty() {
+            true if is_in_check(&next_board, next_board.current_turn.color) => (Wdl::Win, 0),
+            true => (Wdl::Draw, 0),
+            false => {
+                let wdl = tablebase.probe_wdl(&next_board)?.for_opponent();
+                let is_zeroing = board.get_piece_at(chess_move.to).is_some()
+                    || board.get_piece_at(chess_move.from).map(|p| p.piece_type) == Some(PieceType::Pawn);
+                let distance = match is_zeroing {
+                    true => 0,
+                    false => tablebase.probe_dtz(&next_board)?.abs(),
+                };
+                (wdl, distance)
+            },
+        };
+        //Among moves with the same result, shorter wins and longer losses rank higher
+        let rank = match wdl {
+            Wdl::Win | Wdl::CursedWin => -distance,
+            Wdl::Draw => 0,
+            Wdl::BlessedLoss | Wdl::Loss => distance,
+        };
+        let is_better = match best {
+            Some((_, best_wdl, best_rank)) => (wdl, rank) > (best_wdl, best_rank),
+            None => true,
+        };
+        if is_better {
+            best = Some((chess_move, wdl, rank));
+        }
+    }
+    best.map(|(chess_move, wdl, _)| (chess_move, wdl))
+}
+
+//Syzygy file name of the material on the board, such as KRvK, with the side to move's pieces first
+pub fn get_syzygy_name(board: &Board) -> String {
+    let color = board.current_turn.color;
+    format!("{}v{}", get_syzygy_pieces(board, color), get_syzygy_pieces(board, get_enemy_color(color)))
+}
+
+pub(crate) fn get_syzygy_pieces(board: &Board, color: Color) -> String {
+    SYZYGY_PIECE_ORDER.iter()
+        .flat_map(|(piece_type, letter)| {
+            let count = board.squares.iter()
+                .filter(|square| square.piece.is_some_and(|p| p.piece_type == *piece_type && p.color == color))
+                .count();
+            (0..count).map(move |_| *letter)
+        })
+        .collect()
+}
+
+//Positions with as many pieces as the largest tables are only probed with enough depth left, as those
+//tables are the slowest to read
+pub fn should_probe(tablebase: &dyn Tablebase, board: &Board, depth: u32, probe_depth: u32) -> bool {
+    let piece_count = count_pieces(board);
+    let max_pieces = tablebase.max_pieces();
+    piece_count < max_pieces || (piece_count == max_pieces && depth >= cmp::max(probe_depth, 1))
+}
+
+//Knows king and rook against king, where the side with the rook wins unless the rook can be taken.
+//Distances to zeroing are made up so that they shrink as the defending king nears the corner
+#[cfg(test)]
+pub(crate) struct RookEndgameTablebase;
+
+#[cfg(test)]
+impl Tablebase for RookEndgameTablebase {
+    fn max_pieces(&self) -> u32 {
+        3
+    }
+
+    fn probe_wdl(&self, board: &Board) -> Option<Wdl> {
+        match get_syzygy_name(board).as_str() {
+            "KRvK" => Some(Wdl::Win),
+            "KvKR" => Some(Wdl::Loss),
+            "KvK" => Some(Wdl::Draw),
+            _ => None,
+        }
+    }
+
+    fn probe_dtz(&self, board: &Board) -> Option<i32> {
+        let wdl = self.probe_wdl(board)?;
+        let king_index = board.squares.iter()
+            .position(|square| square.piece.is_some_and(|p| p.piece_type == PieceType::King && p.color == Color::Black))?;
+        let distance_to_edge = [king_index % 10 - 1, 8 - king_index % 10, king_index / 10 - 1, 8 - king_index / 10].iter().copied().min()? as i32;
+        match wdl {
+            Wdl::Win => Some(1 + distance_to_edge),
+            Wdl::Loss => Some(-1 - distance_to_edge),
+            _ => Some(0),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    mod probe_root {
+        use super::*;
+
+        #[test]
+        fn it_keeps_the_winning_result() {
+            //The king attacks the rook, which has to move away
+            let board = Board::from_fen("8/8/8/8/8/8/4k3/3R3K w - - 0 1").unwrap();
+            let (chess_move, wdl) = probe_root(&RookEndgameTablebase, &board).unwrap();
+
+            assert_eq!(wdl, Wdl::Win);
+            let next_board = board.test_move(chess_move);
+            assert_eq!(get_syzygy_name(&next_board), "KvKR");
+            assert_eq!(RookEndgameTablebase.probe_dtz(&next_board), Some(-2));
+        }
+
+        #[test]
+        fn it_takes_a_hanging_rook() {
+            let board = Board::from_fen("8/8/8/8/8/k7/4r3/4K3 w - - 0 1").unwrap();
+            let (chess_move, wdl) = probe_root(&RookEndgameTablebase, &board).unwrap();
+            assert_eq!(chess_move, Move::from_chess_move((String::from("e1"), String::from("e2"))));
+            assert_eq!(wdl, Wdl::Draw);
+        }
+
+        #[test]
+        fn it_needs_every_position_to_be_known() {
+            let board = Board::from_fen("8/8/8/4k3/8/8/4P3/R3K3 w - - 0 1").unwrap();
+            assert_eq!(probe_root(&RookEndgameTablebase, &board), None);
+        }
+    }
+
+    mod syzygy_tables {
+        use super::*;
+
+        #[test]
+        fn it_names_the_material_with_the_side_to_move_first() {
+            let board = Board::from_fen("8/8/8/4k3/8/2n5/4P3/R3KB2 b - - 0 1").unwrap();
+            assert_eq!(get_syzygy_name(&board), "KNvKRBP");
+        }
+
+    }
+}
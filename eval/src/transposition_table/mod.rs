@@ -0,0 +1,185 @@
+use std::sync::atomic::{ AtomicU64, Ordering };
+use board::Move;
+
+//Number of slots inspected when estimating how full the table is
+const HASHFULL_SAMPLE_SIZE: usize = 1000;
+
+#[derive(Copy, Clone)]
+#[derive(Debug)]
+#[derive(PartialEq)]
+pub enum Bound {
+    Exact, Lower, Upper
+}
+
+impl Bound {
+    pub fn from_window(value: i32, alpha: i32, beta: i32) -> Bound {
+        if value <= alpha {
+            Bound::Upper
+        } else if value >= beta {
+            Bound::Lower
+        } else {
+            Bound::Exact
+        }
+    }
+}
+
+#[derive(Copy, Clone)]
+#[derive(Debug)]
+#[derive(PartialEq)]
+pub struct CacheEntry {
+    pub depth: u32,
+    pub value: i32,
+    pub bound: Bound,
+    pub best_move: Option<Move>,
+}
+
+impl CacheEntry {
+    //Packed layout: value (32 bits), depth (8), bound (2), best move from (7) and to (7)
+    fn pack(&self) -> u64 {
+        let bound_bits: u64 = match self.bound {
+            Bound::Exact => 1,
+            Bound::Lower => 2,
+            Bound::Upper => 3,
+        };
+        let (from, to) = match self.best_move {
+            Some(best_move) => (best_move.from as u64, best_move.to as u64),
+            None => (0, 0),
+        };
+        (self.value as u32 as u64)
+            | ((self.depth.min(255) as u64) << 32)
+            | (bound_bits << 40)
+            | (from << 42)
+            | (to << 49)
+    }
+
+    fn unpack(data: u64) -> Option<CacheEntry> {
+        let bound = match (data >> 40) & 0b11 {
+            1 => Bound::Exact,
+            2 => Bound::Lower,
+            3 => Bound::Upper,
+            _ => return None,
+        };
+        let from = ((data >> 42) & 0x7F) as usize;
+        let to = ((data >> 49) & 0x7F) as usize;
+        let best_move = match from {
+            0 => None,
+            _ => Some(Move::new(from, to)),
+        };
+        Some(CacheEntry {
+            depth: ((data >> 32) & 0xFF) as u32,
+            value: data as u32 as i32,
+            bound,
+            best_move,
+        })
+    }
+}
+
+#[derive(Default)]
+struct Slot {
+    key: AtomicU64,
+    data: AtomicU64,
+}
+
+//Shared between search threads without locking: each slot stores the key xor'd with its data,
+//so a slot torn by two threads writing at once fails verification instead of returning garbage
+pub struct TranspositionTable {
+    slots: Vec<Slot>,
+}
+
+impl TranspositionTable {
+    pub fn new(size: usize) -> TranspositionTable {
+        let slots = (0..size.max(1)).map(|_| Slot::default()).collect();
+        TranspositionTable { slots }
+    }
+
+    pub fn probe(&self, hash: u64) -> Option<CacheEntry> {
+        let slot = self.get_slot(hash);
+        let data = slot.data.load(Ordering::Relaxed);
+        let key = slot.key.load(Ordering::Relaxed);
+        if key ^ data != hash {
+            return None;
+        }
+        CacheEntry::unpack(data)
+    }
+
+    pub fn store(&self, hash: u64, entry: CacheEntry) {
+        let slot = self.get_slot(hash);
+        let existing_data = slot.data.load(Ordering::Relaxed);
+        let existing_key = slot.key.load(Ordering::Relaxed);
+        if existing_key ^ existing_data == hash {
+            if let Some(existing_entry) = CacheEntry::unpack(existing_data) {
+                if existing_entry.depth > entry.depth {
+                    return;
+                }
+            }
+        }
+        let data = entry.pack();
+        slot.data.store(data, Ordering::Relaxed);
+        slot.key.store(hash ^ data, Ordering::Relaxed);
+    }
+
+    pub fn clear(&self) {
+        for slot in self.slots.iter() {
+            slot.data.store(0, Ordering::Relaxed);
+            slot.key.store(0, Ordering::Relaxed);
+        }
+    }
+
+    //Permille of used slots, estimated from the start of the table as UCI engines do
+    pub fn hashfull(&self) -> u32 {
+        let sample_size = self.slots.len().min(HASHFULL_SAMPLE_SIZE);
+        let used_slots = self.slots[..sample_size].iter()
+            .filter(|slot| slot.data.load(Ordering::Relaxed) != 0)
+            .count();
+        (used_slots * 1000 / sample_size) as u32
+    }
+
+    fn get_slot(&self, hash: u64) -> &Slot {
+        &self.slots[(hash % self.slots.len() as u64) as usize]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    mod transposition_table {
+        use super::*;
+
+        #[test]
+        fn it_returns_stored_entries() {
+            let table = TranspositionTable::new(64);
+            let entry = CacheEntry { depth: 4, value: -350, bound: Bound::Lower, best_move: Some(Move::new(75, 55)) };
+            table.store(12345, entry);
+            assert_eq!(table.probe(12345), Some(entry));
+        }
+
+        #[test]
+        fn it_does_not_return_entries_for_other_keys_sharing_a_slot() {
+            let table = TranspositionTable::new(64);
+            let entry = CacheEntry { depth: 4, value: 20, bound: Bound::Exact, best_move: None };
+            table.store(1, entry);
+            assert_eq!(table.probe(65), None);
+        }
+
+        #[test]
+        fn it_keeps_deeper_entries_for_the_same_position() {
+            let table = TranspositionTable::new(64);
+            let deep_entry = CacheEntry { depth: 6, value: 20, bound: Bound::Exact, best_move: None };
+            let shallow_entry = CacheEntry { depth: 2, value: 90, bound: Bound::Exact, best_move: None };
+            table.store(7, deep_entry);
+            table.store(7, shallow_entry);
+            assert_eq!(table.probe(7), Some(deep_entry));
+        }
+
+        #[test]
+        fn it_forgets_everything_when_cleared() {
+            let table = TranspositionTable::new(64);
+            table.store(7, CacheEntry { depth: 1, value: 0, bound: Bound::Upper, best_move: None });
+            assert!(table.hashfull() > 0);
+            table.clear();
+            assert_eq!(table.probe(7), None);
+            assert_eq!(table.hashfull(), 0);
+        }
+    }
+}
@@ -0,0 +1,279 @@
+use std::fs;
+use std::path::Path;
+use board::Board;
+use board::Piece;
+use board::PieceType;
+use board::square::Color;
+use super::eval_params::{ EvalParams, DEFAULT_EVAL_PARAMS };
+use super::{ get_game_phase, get_snapshot_evaluation, get_terms_evaluation, get_value_of_piece };
+use super::{ pawn_structure, endgame };
+
+const PIECE_TYPES: [PieceType; 6] = [PieceType::Pawn, PieceType::Knight, PieceType::Bishop, PieceType::Rook, PieceType::Queen, PieceType::King];
+const SCALING_CONSTANT_SEARCH_STEPS: u32 = 40;
+const MAX_SCALING_CONSTANT: f64 = 3.0;
+
+//A quiet position with the result of the game it was taken from, from white's point of view
+pub struct TuningPosition {
+    pub board: Board,
+    pub result: f64,
+    pieces: Vec<(usize, Piece)>,
+    phase: u32,
+    //Every term of the evaluation that is not being tuned, from white's perspective and before the endgame
+    //knowledge, which scales the whole evaluation and so has to see the tuned terms too
+    fixed_value: i32,
+}
+
+impl TuningPosition {
+    pub fn new(board: Board, result: f64) -> TuningPosition {
+        let pieces = board.squares.iter()
+            .enumerate()
+            .filter_map(|(board_index, square)| square.piece.map(|p| (board_index, p)))
+            .collect();
+        let phase = get_game_phase(&board);
+        let pawn_structure_value = pawn_structure::evaluate_pawn_structure(&board, phase);
+        let white_value = get_terms_evaluation(&board, &DEFAULT_EVAL_PARAMS, pawn_structure_value, phase);
+        let (white_material, black_material) = get_snapshot_evaluation(&board, &DEFAULT_EVAL_PARAMS);
+        let fixed_value = white_value - (white_material as i32 - black_material as i32);
+        TuningPosition { board, result, pieces, phase, fixed_value }
+    }
+
+    //From white's perspective
+    fn evaluate(&self, params: &EvalParams) -> i32 {
+        let material_value = self.pieces.iter()
+            .map(|(board_index, piece)| {
+                let value = get_value_of_piece(params, *piece, *board_index, self.phase) as i32;
+                match piece.color {
+                    Color::White => value,
+                    Color::Black => -value,
+                }
+            })
+            .sum::<i32>();
+        endgame::evaluate_endgame(&self.board, params, material_value + self.fixed_value)
+    }
+}
+
+//A piece value, or an entry of a piece-square table, which both colors share
+#[derive(Copy, Clone)]
+enum Parameter {
+    Value(PieceType),
+    Entry(PieceType, bool, usize),
+}
+
+impl Parameter {
+    fn get(self, params: &EvalParams) -> u32 {
+        match self {
+            Parameter::Value(piece_type) => params.get_piece_params(piece_type).value,
+            Parameter::Entry(piece_type, is_midgame, board_index) => params.get_piece_params(piece_type).get_square_value(Color::White, is_midgame, board_index),
+        }
+    }
+
+    fn set(self, params: &mut EvalParams, value: u32) {
+        match self {
+            Parameter::Value(piece_type) => params.get_piece_params_mut(piece_type).value = value,
+            Parameter::Entry(piece_type, is_midgame, board_index) => {
+                let piece_params = params.get_piece_params_mut(piece_type);
+                match is_midgame {
+                    true => piece_params.midgame[board_index] = value,
+                    false => piece_params.endgame[board_index] = value,
+                }
+            },
+        }
+    }
+}
+
+//Positions in EPD, each carrying its game result in a c9 opcode such as c9 "1-0";
+pub fn read_epd_file(path: &Path) -> Result<Vec<TuningPosition>, String> {
+    let text = fs::read_to_string(path).map_err(|error| format!("Could not read {}: {}", path.display(), error))?;
+    parse_epd(&text)
+}
+
+pub fn parse_epd(text: &str) -> Result<Vec<TuningPosition>, String> {
+    text.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty() && !line.trim_start().starts_with('#'))
+        .map(|(line_index, line)| {
+            let board = Board::from_fen(line)
+                .ok_or_else(|| format!("Line {}: invalid position", line_index + 1))?;
+            let result = line.split(';')
+                .filter_map(|operation| operation.split_whitespace().skip_while(|token| *token != "c9").nth(1))
+                .next()
+                .and_then(|result| match result.trim_matches('"') {
+                    "1-0" => Some(1.0),
+                    "0-1" => Some(0.0),
+                    "1/2-1/2" => Some(0.5),
+                    _ => None,
+                })
+                .ok_or_else(|| format!("Line {}: missing or invalid c9 result", line_index + 1))?;
+            Ok(TuningPosition::new(board, result))
+        })
+        .collect()
+}
+
+//Mean squared difference between the game results and the results predicted by the evaluation
+pub fn get_error(params: &EvalParams, positions: &[TuningPosition], scaling_constant: f64) -> f64 {
+    let total_error: f64 = positions.iter()
+        .map(|position| {
+            let predicted_result = get_win_probability(position.evaluate(params), scaling_constant);
+            (position.result - predicted_result).powi(2)
+        })
+        .sum();
+    total_error / positions.len().max(1) as f64
+}
+
+//Scales centipawns to win probabilities so that the untuned evaluation fits the results as well as it can.
+//The error is assumed to have a single minimum, which a ternary search homes in on
+pub fn find_scaling_constant(params: &EvalParams, positions: &[TuningPosition]) -> f64 {
+    let (mut low, mut high) = (0.0, MAX_SCALING_CONSTANT);
+    for _ in 0..SCALING_CONSTANT_SEARCH_STEPS {
+        let lower_third = low + (high - low) / 3.0;
+        let upper_third = high - (high - low) / 3.0;
+        if get_error(params, positions, lower_third) < get_error(params, positions, upper_third) {
+            high = upper_third;
+        } else {
+            low = lower_third;
+        }
+    }
+    (low + high) / 2.0
+}
+
+//Texel's local search: every parameter is nudged up or down by one for as long as that lowers the error.
+//Stops after an iteration without any improvement or after max_iterations
+pub fn tune<F: FnMut(u32, f64)>(params: &EvalParams, positions: &[TuningPosition], scaling_constant: f64, max_iterations: u32, mut report: F) -> EvalParams {
+    let mut params = params.clone();
+    let parameters = get_tunable_parameters();
+    let mut best_error = get_error(&params, positions, scaling_constant);
+
+    for iteration in 1..=max_iterations {
+        let mut has_improved = false;
+        for parameter in parameters.iter() {
+            let value = parameter.get(&params);
+            let candidates = [Some(value + 1), value.checked_sub(1)];
+            for candidate in candidates.iter().flatten() {
+                parameter.set(&mut params, *candidate);
+                let error = get_error(&params, positions, scaling_constant);
+                if error < best_error {
+                    best_error = error;
+                    has_improved = true;
+                    break;
+                }
+                parameter.set(&mut params, value);
+            }
+        }
+        report(iteration, best_error);
+        if !has_improved {
+            break;
+        }
+    }
+    params
+}
+
+//The king's value cancels out, and pawns never stand on the first or last rank
+fn get_tunable_parameters() -> Vec<Parameter> {
+    let values = PIECE_TYPES.iter()
+        .filter(|piece_type| **piece_type != PieceType::King)
+        .map(|piece_type| Parameter::Value(*piece_type));
+    let entries = PIECE_TYPES.iter().flat_map(|piece_type| {
+        [true, false].iter().flat_map(move |is_midgame| {
+            (1..=8).flat_map(move |row| (1..=8).map(move |file| row * 10 + file))
+                .filter(move |board_index| *piece_type != PieceType::Pawn || (2..=7).contains(&(board_index / 10)))
+                .map(move |board_index| Parameter::Entry(*piece_type, *is_midgame, board_index))
+        })
+    });
+    values.chain(entries).collect()
+}
+
+fn get_win_probability(value: i32, scaling_constant: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf(-scaling_constant * value as f64 / 400.0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::{ evaluate, evaluate_with_params };
+
+    const EPD: &str = "\
+        # White is a knight up in the first two positions\n\
+        4k3/pppp4/8/8/8/8/PPPP4/3NK3 w - - c9 \"1-0\";\n\
+        3nk3/pppp4/8/8/8/8/PPPP4/4K3 b - - c9 \"0-1\";\n\
+        4k3/pppp4/8/8/8/8/PPPP4/4K3 w - - c9 \"1/2-1/2\";\n";
+
+    mod parse_epd {
+        use super::*;
+
+        #[test]
+        fn it_reads_positions_and_results() {
+            let positions = parse_epd(EPD).unwrap();
+            assert_eq!(positions.len(), 3);
+            assert_eq!(positions[0].result, 1.0);
+            assert_eq!(positions[1].result, 0.0);
+            assert_eq!(positions[2].result, 0.5);
+            assert_eq!(positions[1].board.current_turn.color, Color::Black);
+        }
+
+        #[test]
+        fn it_matches_the_evaluation() {
+            for position in parse_epd(EPD).unwrap() {
+                let white_value = match position.board.current_turn.color {
+                    Color::White => evaluate(&position.board),
+                    Color::Black => -evaluate(&position.board),
+                };
+                assert_eq!(position.evaluate(&DEFAULT_EVAL_PARAMS), white_value);
+            }
+        }
+
+        #[test]
+        fn it_matches_the_evaluation_of_scaled_endings_with_any_params() {
+            let mut params = EvalParams::default();
+            params.pawn.value += 40;
+            params.bishop.value -= 50;
+            params.rook.value += 60;
+            //Opposite-colored bishops, a minor piece up without pawns and a lone king
+            let epd = "\
+                8/5p2/4kb2/8/8/2PB4/1P3P2/4K3 w - - c9 \"1/2-1/2\";\n\
+                8/8/8/4k3/8/3b4/8/R3K3 b - - c9 \"1/2-1/2\";\n\
+                8/1R6/8/8/4k3/8/8/4K3 w - - c9 \"1-0\";\n";
+            for position in parse_epd(epd).unwrap() {
+                let white_value = match position.board.current_turn.color {
+                    Color::White => evaluate_with_params(&position.board, &params),
+                    Color::Black => -evaluate_with_params(&position.board, &params),
+                };
+                assert_eq!(position.evaluate(&params), white_value);
+            }
+        }
+
+        #[test]
+        fn it_rejects_positions_without_a_result() {
+            assert!(parse_epd("4k3/8/8/8/8/8/8/4K3 w - -\n").is_err());
+            assert!(parse_epd("4k3/8/8/8/8/8/8/4K3 w - - c9 \"2-0\";\n").is_err());
+        }
+    }
+
+    mod tune {
+        use super::*;
+
+        #[test]
+        fn it_lowers_the_error() {
+            let positions = parse_epd(EPD).unwrap();
+            let mut params = EvalParams::default();
+            //An undervalued knight makes the evaluation miss the wins
+            params.knight.value = 50;
+            let scaling_constant = 1.0;
+            let initial_error = get_error(&params, &positions, scaling_constant);
+            let mut reported_errors = Vec::new();
+            let tuned_params = tune(&params, &positions, scaling_constant, 2, |_, error| reported_errors.push(error));
+
+            assert_eq!(reported_errors.len(), 2);
+            assert!(get_error(&tuned_params, &positions, scaling_constant) < initial_error);
+            assert_eq!(get_error(&tuned_params, &positions, scaling_constant), reported_errors[1]);
+            assert!(tuned_params.knight.value > params.knight.value);
+        }
+
+        #[test]
+        fn it_tunes_both_colors_alike() {
+            let mut params = EvalParams::default();
+            Parameter::Entry(PieceType::Knight, true, 64).set(&mut params, 77);
+            assert_eq!(params.knight.get_square_value(Color::White, true, 64), 77);
+            assert_eq!(params.knight.get_square_value(Color::Black, true, 34), 77);
+        }
+    }
+}
@@ -1,36 +1,222 @@
 extern crate board;
 extern crate eval;
 use std::env;
+use std::fs;
+use std::path::Path;
+use std::process;
+use std::sync::Arc;
 use board::Move;
 use board::Board;
 use board::square::Color;
-use eval::get_all_legal_moves;
-use eval::Eval;
+use eval::Engine;
+use eval::EngineOptions;
+use eval::SearchResult;
+use eval::san::line_to_san;
+use eval::evaluator::{ Evaluator, ClassicalEvaluator };
+use eval::nnue::{ NnueNetwork, NnueEvaluator };
+use eval::eval_params::EvalParams;
+use eval::tuning;
+use eval::explain;
+use eval::opening_book::{ OpeningBook, PolyglotKeys, BookSelection };
+use eval::book_builder::{ BookBuilder, BookBuilderOptions };
+use eval::tablebase::Tablebase;
+use eval::syzygy::SyzygyTables;
+
+const DEFAULT_TUNING_ITERATIONS: u32 = 100;
 
 fn main() {
     //Example: game 00000000000rnbqkbnr00pppppppp00--------00--------00--------00--------00PPPPPPPP00RNBQKBNR00000000000 -w
+    //Evaluation parameters can be loaded from a JSON file with: game --eval-params params.json [board] [-w|-b]
+    //and tuned against labelled positions with: game [--eval-params params.json] tune positions.epd tuned.json [iterations].
+    //A neural network evaluation is used instead with: game --nnue network.nnue [board] [-w|-b]
+    //The classical evaluation of a position is broken down term by term with: game eval <fen> [--json]
+    //A Polyglot opening book is used with: game --book book.bin [--book-depth plies] [--book-best] [board] [-w|-b]
+    //and built from a PGN collection with: game make-book games.pgn book.bin [--max-plies plies] [--min-games games] [--min-score score]
+    //Syzygy endgame tables are probed with: game --syzygy-path directory [board] [-w|-b]
+
+    let mut args: Vec<String> = env::args().collect();
+    let eval_params = match take_flag_value(&mut args, "--eval-params") {
+        Some(path) => EvalParams::load(Path::new(&path)).unwrap_or_else(|error| exit_with_error(&error.to_string())),
+        None => EvalParams::default(),
+    };
+    let nnue_path = take_flag_value(&mut args, "--nnue");
+    let mut engine_options = EngineOptions::default();
+    if let Some(book_depth) = take_flag_value(&mut args, "--book-depth") {
+        engine_options.book_depth = book_depth.parse().unwrap_or_else(|_| exit_with_error("The book depth must be a number of plies"));
+    }
+    if take_flag(&mut args, "--book-best") {
+        engine_options.book_selection = BookSelection::Best;
+    }
+    let book_path = take_flag_value(&mut args, "--book");
+    let syzygy_path = take_flag_value(&mut args, "--syzygy-path");
+    match args.get(1).map(String::as_str) {
+        Some("tune") => {
+            run_tuning(&args[2..], eval_params);
+            return;
+        },
+        Some("eval") => {
+            print_evaluation(&args[2..], &eval_params);
+            return;
+        },
+        Some("make-book") => {
+            build_opening_book(&mut args);
+            return;
+        },
+        _ => {},
+    }
+    let opening_book = book_path.map(|book_path| {
+        OpeningBook::load(Path::new(&book_path), PolyglotKeys::default()).unwrap_or_else(|error| exit_with_error(&error.to_string()))
+    });
+    let tablebase = syzygy_path.map(|syzygy_path| -> Arc<dyn Tablebase> {
+        Arc::new(SyzygyTables::scan(Path::new(&syzygy_path)).unwrap_or_else(|error| exit_with_error(&error.to_string())))
+    });
 
-    let args: Vec<String> = env::args().collect();
-    if args.len() == 1 {
+    let board = match args.len() {
         //Running binary without args -- Example of interacting with board and receiving an evaluation
-        let board_string = String::from("00000000000rnbqkbnr00pppppppp00--------00--------00--------00--------00PPPPPPPP00RNBQKBNR00000000000");
-        let mut board = Board::new(board_string, Color::White);
-        board.make_move(Move::from_chess_move((String::from("d2"), String::from("d4"))));
-        board.make_move(Move::from_chess_move((String::from("e7"), String::from("e5"))));
-        board.make_move(Move::from_chess_move((String::from("h2"), String::from("h4"))));
-        board.make_move(Move::from_chess_move((String::from("h7"), String::from("h5"))));
-        board.make_move(Move::from_chess_move((String::from("c1"), String::from("g5"))));        
-        let evaluation = Eval::new(&board, 5);
-        println!("{}", evaluation.unwrap());
-    } else {
-        let board_string = String::from(args[1].clone());
-        let current_color: Color = match args[2].as_ref() {
-            "-b" => Color::Black,
-            _ => Color:: White,
-        };
-        let mut board: Board = Board::new(board_string, current_color);
-        let legal_moves = get_all_legal_moves(&board);
-        let evaluation = Eval::new(&board, 5);
-        println!("{}", evaluation.unwrap());
+        1 => {
+            let board_string = String::from("00000000000rnbqkbnr00pppppppp00--------00--------00--------00--------00PPPPPPPP00RNBQKBNR00000000000");
+            let mut board = Board::new(board_string, Color::White);
+            board.make_move(Move::from_chess_move((String::from("d2"), String::from("d4"))));
+            board.make_move(Move::from_chess_move((String::from("e7"), String::from("e5"))));
+            board.make_move(Move::from_chess_move((String::from("h2"), String::from("h4"))));
+            board.make_move(Move::from_chess_move((String::from("h7"), String::from("h5"))));
+            board.make_move(Move::from_chess_move((String::from("c1"), String::from("g5"))));
+            board
+        },
+        _ => {
+            let board_string = args[1].clone();
+            //White moves when no color is given
+            let current_color: Color = match args.get(2).map(String::as_str) {
+                Some("-b") => Color::Black,
+                _ => Color:: White,
+            };
+            Board::new(board_string, current_color)
+        },
+    };
+
+    //The network replaces the classical evaluation as a whole, so it ignores --eval-params
+    match nnue_path {
+        Some(path) => {
+            let network = NnueNetwork::load(Path::new(&path)).unwrap_or_else(|error| exit_with_error(&error.to_string()));
+            search(Engine::with_evaluator(engine_options, NnueEvaluator::new(Arc::new(network))), &board, opening_book, tablebase);
+        },
+        None => search(Engine::with_evaluator(engine_options, ClassicalEvaluator::with_params(eval_params)), &board, opening_book, tablebase),
+    }
+}
+
+fn search<E: Evaluator>(mut engine: Engine<E>, board: &Board, opening_book: Option<OpeningBook>, tablebase: Option<Arc<dyn Tablebase>>) {
+    engine.set_opening_book(opening_book);
+    engine.set_tablebase(tablebase);
+    let search_result = engine.search(board, 5);
+    print_search_result(board, &search_result);
+}
+
+//Removes a flag and the value following it from the arguments
+fn take_flag_value(args: &mut Vec<String>, flag: &str) -> Option<String> {
+    let flag_index = args.iter().position(|arg| arg == flag)?;
+    match args.get(flag_index + 1) {
+        Some(value) => {
+            let value = value.clone();
+            args.drain(flag_index..flag_index + 2);
+            Some(value)
+        },
+        None => exit_with_error(&format!("{} needs a value", flag)),
+    }
+}
+
+//Removes a flag without a value from the arguments
+fn take_flag(args: &mut Vec<String>, flag: &str) -> bool {
+    match args.iter().position(|arg| arg == flag) {
+        Some(flag_index) => {
+            args.remove(flag_index);
+            true
+        },
+        None => false,
+    }
+}
+
+fn exit_with_error(message: &str) -> ! {
+    eprintln!("{}", message);
+    process::exit(1);
+}
+
+fn run_tuning(args: &[String], eval_params: EvalParams) {
+    if args.len() < 2 {
+        exit_with_error("Usage: game tune <positions.epd> <output.json> [iterations]");
+    }
+    let max_iterations = match args.get(2) {
+        Some(iterations) => match iterations.parse() {
+            Ok(iterations) if iterations > 0 => iterations,
+            _ => exit_with_error("The number of iterations must be a positive integer"),
+        },
+        None => DEFAULT_TUNING_ITERATIONS,
+    };
+    let positions = tuning::read_epd_file(Path::new(&args[0])).unwrap_or_else(|error| exit_with_error(&error));
+
+    let scaling_constant = tuning::find_scaling_constant(&eval_params, &positions);
+    println!("positions {} scaling constant {:.4} error {:.6}", positions.len(), scaling_constant,
+        tuning::get_error(&eval_params, &positions, scaling_constant));
+    let output_path = Path::new(&args[1]);
+    let tuned_params = tuning::tune(&eval_params, &positions, scaling_constant, max_iterations, |iteration, error| {
+        println!("iteration {} error {:.6}", iteration, error);
+    });
+    if let Err(error) = tuned_params.save(output_path) {
+        exit_with_error(&format!("Could not write {}: {}", output_path.display(), error));
+    }
+}
+
+fn build_opening_book(args: &mut Vec<String>) {
+    let mut options = BookBuilderOptions::default();
+    if let Some(max_plies) = take_flag_value(args, "--max-plies") {
+        options.max_plies = max_plies.parse().unwrap_or_else(|_| exit_with_error("The maximum number of plies must be a positive integer"));
+    }
+    if let Some(min_games) = take_flag_value(args, "--min-games") {
+        options.min_games = min_games.parse().unwrap_or_else(|_| exit_with_error("The minimum number of games must be a positive integer"));
+    }
+    if let Some(min_score) = take_flag_value(args, "--min-score") {
+        options.min_score = min_score.parse().unwrap_or_else(|_| exit_with_error("The minimum score must be a number between 0 and 1"));
+    }
+    if args.len() < 4 {
+        exit_with_error("Usage: game make-book <games.pgn> <book.bin> [--max-plies plies] [--min-games games] [--min-score score]");
+    }
+    let pgn_path = Path::new(&args[2]);
+    let pgn = fs::read_to_string(pgn_path)
+        .unwrap_or_else(|error| exit_with_error(&format!("Could not read {}: {}", pgn_path.display(), error)));
+
+    let mut builder = BookBuilder::new(PolyglotKeys::default(), options);
+    let game_count = builder.add_pgn(&pgn);
+    let opening_book = builder.build();
+    println!("games {} entries {}", game_count, opening_book.entries().len());
+    let output_path = Path::new(&args[3]);
+    if let Err(error) = opening_book.save(output_path) {
+        exit_with_error(&format!("Could not write {}: {}", output_path.display(), error));
+    }
+}
+
+//The FEN may be passed as one quoted argument or as several
+fn print_evaluation(args: &[String], eval_params: &EvalParams) {
+    let is_json = args.iter().any(|arg| arg == "--json");
+    let fen: Vec<&str> = args.iter().map(String::as_str).filter(|arg| *arg != "--json").collect();
+    let board = Board::from_fen(&fen.join(" "))
+        .unwrap_or_else(|| exit_with_error("Usage: game eval <fen> [--json]"));
+    let breakdown = explain::explain_with_params(&board, eval_params);
+    match is_json {
+        true => println!("{}", breakdown.to_json()),
+        false => println!("{}", breakdown),
+    }
+}
+
+fn print_search_result(board: &Board, search_result: &SearchResult) {
+    println!("info depth {} seldepth {} score {} nodes {} time {} hashfull {} pv {}",
+        search_result.depth,
+        search_result.seldepth,
+        search_result.score,
+        search_result.nodes,
+        search_result.time.as_millis(),
+        search_result.hashfull,
+        line_to_san(board, &search_result.pv).join(" "));
+    match search_result.best_move {
+        Some(best_move) => println!("{}", best_move),
+        None => println!("No legal moves"),
     }
 }